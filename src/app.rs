@@ -1,4 +1,9 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    f32::consts::SQRT_2,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use winit::{
     application::ApplicationHandler,
@@ -12,20 +17,58 @@ use pixels::{Pixels, SurfaceTexture};
 
 use crate::{
     camera::Camera,
-    math::{rotate_around_pivot, rotate_vertex},
+    hud::Hud,
+    loader::load_wavefront,
     mesh::Mesh,
-    primitives::{colour::Colour, vector::Vec3, vertex::Vertex},
+    post::{AntiAliasing, Fxaa, Outline, PostChain, Vignette},
+    primitives::{colour::Colour, quaternion::Quat, vector::Vec3},
     renderer::Renderer,
+    resolution::{RenderScale, resample},
+    scene::{Instance, MeshId, NodeContent, NodeId, Scene},
+    state::DepthState,
+    taa::Taa,
+    target::RenderTarget,
+    transform::Transform,
+    watcher::FileWatcher,
 };
 
+/// How often the files behind loaded meshes are checked for changes.
+const RELOAD_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often the window title is refreshed while showing stats.
+const TITLE_INTERVAL: Duration = Duration::from_millis(250);
+
+const TITLE: &str = "Window";
+
+/// Frame time that dynamic resolution scaling aims for, 60 fps.
+const TARGET_FRAME_TIME: Duration = Duration::from_nanos(16_666_667);
+
+/// Factor each render scale key press changes the scale by.
+const SCALE_STEP: f64 = 1.25;
+
 #[derive(Debug)]
 pub struct App {
     window: Option<Arc<Window>>,
     pixels: Option<Pixels<'static>>,
+    /// Draws at the window size times `scale`.
     renderer: Renderer,
+    /// The frame resampled to the window size, with the HUD on top. Only
+    /// used while the two sizes differ.
+    display: RenderTarget,
+    window_size: (usize, usize),
+    scale: RenderScale,
     camera: Camera,
-    meshes: Vec<Mesh>,
-    angles: (f32, f32, f32),
+    scene: Scene,
+    watchers: Vec<(MeshId, FileWatcher)>,
+    last_reload_poll: Instant,
+    show_stats: bool,
+    last_title_update: Instant,
+    hud: Hud,
+    post: PostChain,
+    post_enabled: bool,
+    anti_aliasing: AntiAliasing,
+    fxaa: PostChain,
+    taa: Taa,
 }
 
 impl App {
@@ -34,11 +77,182 @@ impl App {
             window: None,
             pixels: None,
             renderer: Renderer::new(width, height),
+            display: RenderTarget::default(),
+            window_size: (width, height),
+            scale: RenderScale::default(),
             camera: Camera::new(width, height),
-            meshes: Vec::new(),
-            angles: (0.0, 0.0, 0.0),
+            scene: Scene::new(),
+            watchers: Vec::new(),
+            last_reload_poll: Instant::now(),
+            show_stats: false,
+            last_title_update: Instant::now(),
+            hud: Hud::new(),
+            post: Self::default_post_chain(),
+            post_enabled: false,
+            anti_aliasing: AntiAliasing::default(),
+            fxaa: Self::fxaa_chain(),
+            taa: Taa::new(),
+        }
+    }
+
+    fn default_post_chain() -> PostChain {
+        let mut post = PostChain::new();
+        post.push(Outline::default()).push(Vignette::default());
+        post
+    }
+
+    fn fxaa_chain() -> PostChain {
+        let mut fxaa = PostChain::new();
+        fxaa.push(Fxaa::default());
+        fxaa
+    }
+
+    fn cycle_anti_aliasing(&mut self) {
+        self.anti_aliasing = self.anti_aliasing.next();
+        self.camera.jitter = (0.0, 0.0);
+        self.taa.reset();
+    }
+
+    fn rotate_meshes(&mut self, axis: Vec3) {
+        let step = Quat::from_axis_angle(axis, 0.2);
+        let roots: Vec<NodeId> = self.scene.roots().collect();
+
+        for id in roots {
+            self.scene.node_mut(id).transform.rotate(step);
+        }
+    }
+
+    /// Replaces the starting camera. Its screen size follows the render
+    /// resolution.
+    pub fn set_camera(&mut self, camera: Camera) {
+        self.camera = Camera {
+            screen_width: self.renderer.width,
+            screen_height: self.renderer.height,
+            ..camera
+        };
+    }
+
+    /// Changes the internal render resolution relative to the window.
+    pub fn set_render_scale(&mut self, scale: RenderScale) {
+        self.scale = scale;
+        self.resize_render();
+    }
+
+    /// Steps the fixed render scale by `factor`, leaving dynamic scaling.
+    fn step_render_scale(&mut self, factor: f64) {
+        let scale = &self.scale;
+        let value = (scale.scale * factor).clamp(scale.min, scale.max);
+        self.set_render_scale(RenderScale::fixed(value));
+    }
+
+    fn toggle_dynamic_scale(&mut self) {
+        let mut scale = match self.scale.target {
+            Some(_) => RenderScale::fixed(1.0),
+            None => RenderScale::dynamic(TARGET_FRAME_TIME),
+        };
+        scale.scale = self.scale.scale;
+        self.set_render_scale(scale);
+    }
+
+    /// Sizes the renderer and camera for the window at the current scale.
+    fn resize_render(&mut self) {
+        let (width, height) = self.scale.size(self.window_size.0, self.window_size.1);
+
+        if (width, height) != (self.renderer.width, self.renderer.height) {
+            self.renderer.resize(width, height);
+        }
+        self.camera.screen_width = width;
+        self.camera.screen_height = height;
+
+        if (self.display.width, self.display.height) != self.window_size
+            && (width, height) != self.window_size
+        {
+            self.display.resize(self.window_size.0, self.window_size.1);
+        }
+    }
+
+    /// Copies the finished frame into `frame` at the window size, drawing
+    /// the HUD at full resolution on top.
+    fn present(&mut self, frame: &mut [u8]) {
+        let size = (self.renderer.width, self.renderer.height);
+
+        if size == self.window_size {
+            self.hud.draw(&mut self.renderer, &self.camera, &self.scene);
+            frame.copy_from_slice(&self.renderer.buffer);
+            return;
+        }
+
+        let text = self.hud.text(&self.renderer, &self.camera, &self.scene);
+        resample(
+            &self.renderer.buffer,
+            size,
+            &mut self.display.buffer,
+            self.window_size,
+        );
+
+        // A raw swap rather than `render_to`: the HUD only writes 8-bit
+        // colour, so the display needs no HDR or multisample buffers.
+        self.renderer.swap_target(&mut self.display);
+        self.hud.draw_text(&mut self.renderer, &text);
+        self.renderer.swap_target(&mut self.display);
+        frame.copy_from_slice(&self.display.buffer);
+    }
+
+    /// Loads an OBJ into the scene and watches its files for hot reload.
+    pub fn add_model(&mut self, path: &Path, transform: Transform) -> Result<(), String> {
+        let mesh = load_wavefront(path)?;
+        let name = path
+            .file_stem()
+            .map_or(String::from("model"), |s| s.to_string_lossy().into_owned());
+
+        self.add_mesh(&name, transform, mesh);
+        Ok(())
+    }
+
+    fn add_mesh(&mut self, name: &str, transform: Transform, mesh: Mesh) {
+        let watcher = FileWatcher::new(&mesh.sources);
+        let mesh = self.scene.add_mesh(mesh);
+        self.scene
+            .add(name, transform, NodeContent::Mesh(Instance::new(mesh)));
+        self.watchers.push((mesh, watcher));
+    }
+
+    fn toggle_stats(&mut self) {
+        self.show_stats = !self.show_stats;
+        self.renderer.profile_stages = self.show_stats;
+
+        if !self.show_stats
+            && let Some(window) = &self.window
+        {
+            window.set_title(TITLE);
+        }
+    }
+
+    fn update_title(&mut self, frame_time: Duration) {
+        if !self.show_stats || self.last_title_update.elapsed() < TITLE_INTERVAL {
+            return;
+        }
+        self.last_title_update = Instant::now();
+
+        if let Some(window) = &self.window {
+            window.set_title(&format!(
+                "{TITLE} | {:.2} ms | {}",
+                frame_time.as_secs_f64() * 1000.0,
+                self.renderer.stats.summary()
+            ));
         }
     }
+
+    /// Re-imports any mesh whose OBJ, MTL or texture files changed on disk,
+    /// at most once per [`RELOAD_POLL_INTERVAL`].
+    fn reload_changed_meshes(&mut self) {
+        if self.last_reload_poll.elapsed() < RELOAD_POLL_INTERVAL {
+            return;
+        }
+        self.last_reload_poll = Instant::now();
+
+        reload_changed(&mut self.scene, &mut self.watchers);
+    }
 }
 
 impl ApplicationHandler for App {
@@ -46,40 +260,22 @@ impl ApplicationHandler for App {
         let window = event_loop
             .create_window(
                 WindowAttributes::default()
-                    .with_title("Window")
+                    .with_title(TITLE)
                     .with_inner_size(winit::dpi::LogicalSize::new(
-                        self.renderer.width as f64,
-                        self.renderer.height as f64,
+                        self.window_size.0 as f64,
+                        self.window_size.1 as f64,
                     )),
             )
             .unwrap();
 
         let window = Arc::new(window);
 
-        let surface = SurfaceTexture::new(
-            self.renderer.width as u32,
-            self.renderer.height as u32,
-            window.clone(),
-        );
-
-        let pixels = Pixels::new(
-            self.renderer.width as u32,
-            self.renderer.height as u32,
-            surface,
-        )
-        .unwrap();
+        let (width, height) = (self.window_size.0 as u32, self.window_size.1 as u32);
+        let surface = SurfaceTexture::new(width, height, window.clone());
+        let pixels = Pixels::new(width, height, surface).unwrap();
 
         self.window = Some(window);
         self.pixels = Some(pixels);
-
-        self.meshes.push(Mesh::custom(
-            Path::new("assets/objects/skull.obj"),
-            Vec3 {
-                x: 0.0,
-                y: 0.0,
-                z: 10.0,
-            },
-        ))
     }
 
     fn window_event(
@@ -119,69 +315,89 @@ impl ApplicationHandler for App {
                         Key::Character(ref c) if c == "q" => self.camera.position.y += 0.2,
                         Key::Character(ref c) if c == "e" => self.camera.position.y -= 0.2,
 
-                        Key::Character(ref c) if c == "x" => self.angles.0 += 0.2,
-                        Key::Character(ref c) if c == "c" => self.angles.1 += 0.2,
-                        Key::Character(ref c) if c == "z" => self.angles.2 += 0.2,
+                        Key::Character(ref c) if c == "x" => self.rotate_meshes(Vec3::X),
+                        Key::Character(ref c) if c == "c" => self.rotate_meshes(Vec3::Y),
+                        Key::Character(ref c) if c == "z" => self.rotate_meshes(Vec3::Z),
+
+                        Key::Character(ref c) if c == "i" => self.toggle_stats(),
+                        Key::Character(ref c) if c == "h" => self.hud.visible = !self.hud.visible,
+                        Key::Character(ref c) if c == "m" => {
+                            self.renderer.polygon_mode = self.renderer.polygon_mode.next()
+                        }
+                        Key::Character(ref c) if c == "b" => {
+                            self.renderer.cull_mode = self.renderer.cull_mode.next()
+                        }
+                        Key::Character(ref c) if c == "f" => {
+                            self.renderer.front_face = self.renderer.front_face.flipped()
+                        }
+                        Key::Character(ref c) if c == "p" => self.post_enabled = !self.post_enabled,
+                        Key::Character(ref c) if c == "t" => {
+                            self.renderer.set_hdr(self.renderer.hdr.is_none())
+                        }
+                        Key::Character(ref c) if c == "g" => {
+                            self.renderer.tone_mapper = self.renderer.tone_mapper.next()
+                        }
+                        Key::Character(ref c) if c == "n" => {
+                            self.renderer.set_msaa(self.renderer.msaa().next())
+                        }
+                        Key::Character(ref c) if c == "-" => {
+                            self.step_render_scale(1.0 / SCALE_STEP)
+                        }
+                        Key::Character(ref c) if c == "=" => self.step_render_scale(SCALE_STEP),
+                        Key::Character(ref c) if c == "v" => self.toggle_dynamic_scale(),
+                        Key::Character(ref c) if c == "y" => self.cycle_anti_aliasing(),
+                        Key::Character(ref c) if c == "[" => self.renderer.exposure /= SQRT_2,
+                        Key::Character(ref c) if c == "]" => self.renderer.exposure *= SQRT_2,
+                        Key::Character(ref c) if c == "r" => {
+                            let mode = self.renderer.depth_state.mode.next();
+                            self.renderer.depth_state = DepthState {
+                                mode,
+                                compare: mode.nearer(),
+                                ..self.renderer.depth_state
+                            };
+                        }
                         _ => {}
                     }
                 }
             }
 
             WindowEvent::RedrawRequested => {
+                self.reload_changed_meshes();
+
+                let frame_start = Instant::now();
                 let bg_colour = Colour::new(0, 0, 0, 255);
 
+                if self.anti_aliasing == AntiAliasing::Taa {
+                    self.taa.jitter(&mut self.camera);
+                }
+
+                self.renderer.begin_frame();
                 self.renderer.clear(bg_colour);
 
-                for mesh in &self.meshes {
-                    let pivot = if mesh.rotate_around_pivot {
-                        Some(mesh.pivot.as_ref().unwrap())
-                    } else {
-                        None
-                    };
-
-                    let world_vertices: Vec<Vertex> = mesh
-                        .vertices
-                        .iter()
-                        .map(|v| {
-                            let local_rotated = rotate_vertex(v, self.angles);
-
-                            let mut world = Vertex::new(
-                                local_rotated.x + mesh.centre.x,
-                                local_rotated.y + mesh.centre.y,
-                                local_rotated.z + mesh.centre.z,
-                                local_rotated.u,
-                                local_rotated.v,
-                            );
-
-                            if let Some(p) = pivot {
-                                world = rotate_around_pivot(&world, p, self.angles);
-                            }
-
-                            world
-                        })
-                        .collect();
-
-                    let view_vertices: Vec<Option<Vertex>> = world_vertices
-                        .iter()
-                        .map(|v| self.camera.project_perspective(v))
-                        .collect();
-
-                    for triangle in &mesh.triangles {
-                        if let (Some(v0), Some(v1), Some(v2), m) = (
-                            &view_vertices[triangle.i0],
-                            &view_vertices[triangle.i1],
-                            &view_vertices[triangle.i2],
-                            &triangle.material_index,
-                        ) {
-                            self.renderer.fill_triangle(v0, v1, v2, &mesh.materials[*m]);
-                        }
-                    }
+                self.renderer.draw_scene(&self.scene, &self.camera);
+                self.renderer.resolve();
+                match self.anti_aliasing {
+                    AntiAliasing::Off => {}
+                    AntiAliasing::Fxaa => self.fxaa.apply(&mut self.renderer),
+                    AntiAliasing::Taa => self.taa.resolve(&mut self.renderer, &self.camera),
+                }
+                if self.post_enabled {
+                    self.post.apply(&mut self.renderer);
                 }
 
-                if let Some(pixels) = &mut self.pixels {
-                    let frame = pixels.frame_mut();
-                    frame.copy_from_slice(&self.renderer.buffer);
+                if let Some(mut pixels) = self.pixels.take() {
+                    let present_start = Instant::now();
+                    self.present(pixels.frame_mut());
                     pixels.render().unwrap();
+                    self.renderer.stats.timings.present += present_start.elapsed();
+                    self.pixels = Some(pixels);
+                }
+
+                let frame_time = frame_start.elapsed();
+                self.hud.record_frame(frame_time);
+                self.update_title(frame_time);
+                if self.scale.update(frame_time) {
+                    self.resize_render();
                 }
 
                 if let Some(window) = &self.window {
@@ -190,7 +406,8 @@ impl ApplicationHandler for App {
             }
 
             WindowEvent::Resized(size) => {
-                self.renderer = Renderer::new(size.width as usize, size.height as usize);
+                self.window_size = (size.width as usize, size.height as usize);
+                self.resize_render();
                 if let Some(pixels) = &mut self.pixels {
                     pixels.resize_surface(size.width, size.height).unwrap();
                     pixels.resize_buffer(size.width, size.height).unwrap();
@@ -204,3 +421,89 @@ impl ApplicationHandler for App {
         }
     }
 }
+
+/// Reloads each mesh whose watcher saw a change and watches its new set of
+/// files. A mesh that fails to reload keeps its previous contents.
+fn reload_changed(scene: &mut Scene, watchers: &mut [(MeshId, FileWatcher)]) {
+    for (id, watcher) in watchers.iter_mut() {
+        if !watcher.poll() {
+            continue;
+        }
+
+        let mesh = scene.mesh_mut(*id);
+
+        match mesh.reload() {
+            Ok(()) => watcher.watch(&mesh.sources),
+            Err(e) => eprintln!("Failed to reload {:?}: {}", mesh.sources.first(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::PathBuf,
+        time::{Duration, SystemTime},
+    };
+
+    use super::*;
+
+    const TRIANGLE: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\n\
+                            f 1/1/1 2/1/1 3/1/1\n";
+    const QUAD: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\n\
+                        f 1/1/1 2/1/1 3/1/1\nf 1/1/1 3/1/1 4/1/1\n";
+    /// A face that refers to vertices the file does not have.
+    const BROKEN: &str = "vt 0 0\nvn 0 0 1\nf 1/1/1 2/1/1 3/1/1\n";
+
+    /// Writes `contents` to `path` with a modification time `seconds`
+    /// after the epoch, so successive writes are always seen as changes.
+    fn write(path: &Path, contents: &str, seconds: u64) {
+        fs::write(path, contents).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+            .unwrap();
+    }
+
+    fn temp_obj(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("software_renderer_{test}_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("model.obj")
+    }
+
+    #[test]
+    fn changed_meshes_are_reloaded_and_broken_ones_kept() {
+        let path = temp_obj("app_reload");
+        write(&path, TRIANGLE, 1);
+
+        let mut scene = Scene::new();
+        let mesh = load_wavefront(&path).unwrap();
+        let watcher = FileWatcher::new(&mesh.sources);
+        let id = scene.add_mesh(mesh);
+        let mut watchers = vec![(id, watcher)];
+
+        // Nothing changed yet.
+        reload_changed(&mut scene, &mut watchers);
+        assert_eq!(scene.mesh(id).triangles.len(), 1);
+
+        write(&path, QUAD, 2);
+        reload_changed(&mut scene, &mut watchers);
+        assert_eq!(scene.mesh(id).triangles.len(), 2);
+        assert_eq!(scene.mesh(id).vertices.len(), 4);
+
+        write(&path, BROKEN, 3);
+        reload_changed(&mut scene, &mut watchers);
+        assert_eq!(scene.mesh(id).triangles.len(), 2);
+
+        // Fixing the file picks it up again.
+        write(&path, TRIANGLE, 4);
+        reload_changed(&mut scene, &mut watchers);
+        assert_eq!(scene.mesh(id).triangles.len(), 1);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
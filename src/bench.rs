@@ -0,0 +1,278 @@
+use std::{
+    f64::consts::TAU,
+    fmt,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    camera::Camera,
+    loader::load_wavefront,
+    primitives::{colour::Colour, vector::Vec3},
+    renderer::Renderer,
+    scene::{Instance, NodeContent, Scene},
+    stats::RenderStats,
+    transform::Transform,
+};
+
+/// The model sits this many bounding radii from the camera path's centre.
+const ORBIT_DISTANCE: f64 = 2.2;
+
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    pub model: PathBuf,
+    pub frames: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            model: PathBuf::new(),
+            frames: 240,
+            width: 600,
+            height: 600,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    pub options: BenchOptions,
+    pub min: Duration,
+    pub mean: Duration,
+    pub p99: Duration,
+    /// Counters summed over every frame.
+    pub stats: RenderStats,
+}
+
+/// Renders `options.frames` frames of the model while the camera makes one
+/// orbit around it, timing each frame. Nothing is presented.
+pub fn run(options: &BenchOptions) -> Result<BenchReport, String> {
+    if options.frames == 0 {
+        return Err(String::from("Benchmark needs at least one frame"));
+    }
+
+    let mesh = load_wavefront(&options.model)?;
+    let distance = mesh.bounding_radius() * ORBIT_DISTANCE;
+
+    let mut scene = Scene::new();
+    let mesh = scene.add_mesh(mesh);
+    scene.add(
+        "model",
+        Transform::default(),
+        NodeContent::Mesh(Instance::new(mesh)),
+    );
+
+    let mut renderer = Renderer::new(options.width, options.height);
+    let mut camera = Camera::new(options.width, options.height);
+    let mut times = Vec::with_capacity(options.frames);
+    let mut stats = RenderStats::default();
+
+    for frame in 0..options.frames {
+        let angle = TAU * frame as f64 / options.frames as f64;
+        camera.position = Vec3::new(angle.sin(), 0.3, -angle.cos()) * distance;
+        camera.look_at(Vec3::default());
+
+        let start = Instant::now();
+        renderer.begin_frame();
+        renderer.clear(Colour::new(0, 0, 0, 255));
+        renderer.draw_scene(&scene, &camera);
+        times.push(start.elapsed());
+
+        stats += renderer.stats;
+    }
+
+    times.sort();
+
+    Ok(BenchReport {
+        options: options.clone(),
+        min: times[0],
+        mean: times.iter().sum::<Duration>() / times.len() as u32,
+        p99: p99(&times),
+        stats,
+    })
+}
+
+/// The 99th percentile of sorted, non-empty `times`: the smallest time at
+/// least 99% of frames are no slower than.
+fn p99(times: &[Duration]) -> Duration {
+    times[(times.len() * 99).div_ceil(100) - 1]
+}
+
+impl BenchReport {
+    pub fn to_json(&self) -> String {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+
+        format!(
+            "{{\n  \"model\": {},\n  \"frames\": {},\n  \"width\": {},\n  \"height\": {},\n  \
+             \"frame_time_ms\": {{ \"min\": {:.4}, \"mean\": {:.4}, \"p99\": {:.4} }},\n  \
+             \"triangles_submitted\": {},\n  \"triangles_culled\": {},\n  \
+             \"triangles_clipped\": {},\n  \"triangles_rasterized\": {},\n  \
+             \"pixels_shaded\": {}\n}}\n",
+            json_string(&self.options.model.to_string_lossy()),
+            self.options.frames,
+            self.options.width,
+            self.options.height,
+            ms(self.min),
+            ms(self.mean),
+            ms(self.p99),
+            self.stats.triangles_submitted,
+            self.stats.triangles_culled,
+            self.stats.triangles_clipped,
+            self.stats.triangles_rasterized,
+            self.stats.pixels_shaded,
+        )
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let frames = self.options.frames as u64;
+
+        writeln!(
+            f,
+            "{} at {}x{}, {} frames",
+            self.options.model.display(),
+            self.options.width,
+            self.options.height,
+            frames
+        )?;
+        writeln!(
+            f,
+            "frame time    min {:.3} ms, mean {:.3} ms, p99 {:.3} ms",
+            ms(self.min),
+            ms(self.mean),
+            ms(self.p99)
+        )?;
+        writeln!(
+            f,
+            "triangles     {} submitted, {} culled, {} clipped, {} rasterized ({} per frame)",
+            self.stats.triangles_submitted,
+            self.stats.triangles_culled,
+            self.stats.triangles_clipped,
+            self.stats.triangles_rasterized,
+            self.stats.triangles_rasterized / frames
+        )?;
+        write!(
+            f,
+            "pixels shaded {} ({} per frame)",
+            self.stats.pixels_shaded,
+            self.stats.pixels_shaded / frames
+        )
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn millis(count: u64) -> Vec<Duration> {
+        (1..=count).map(Duration::from_millis).collect()
+    }
+
+    #[test]
+    fn p99_picks_the_frame_below_the_slowest_percent() {
+        assert_eq!(p99(&millis(1)), Duration::from_millis(1));
+        assert_eq!(p99(&millis(2)), Duration::from_millis(2));
+        assert_eq!(p99(&millis(100)), Duration::from_millis(99));
+        assert_eq!(p99(&millis(101)), Duration::from_millis(100));
+        assert_eq!(p99(&millis(1000)), Duration::from_millis(990));
+    }
+
+    #[test]
+    fn zero_frames_is_an_error() {
+        let options = BenchOptions {
+            frames: 0,
+            ..BenchOptions::default()
+        };
+
+        assert!(run(&options).is_err());
+    }
+
+    #[test]
+    fn run_sums_the_counters_of_every_frame() {
+        let options = BenchOptions {
+            model: Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/objects/cube.obj"),
+            frames: 3,
+            width: 32,
+            height: 32,
+        };
+        let report = run(&options).unwrap();
+
+        assert_eq!(report.stats.triangles_submitted, 3 * 12);
+        assert!(report.stats.triangles_rasterized > 0);
+        assert!(report.min <= report.mean && report.mean <= report.p99);
+    }
+
+    #[test]
+    fn json_strings_are_escaped() {
+        assert_eq!(json_string("plain"), r#""plain""#);
+        assert_eq!(json_string(r#"a "b" c"#), r#""a \"b\" c""#);
+        assert_eq!(json_string(r"C:\models\a.obj"), r#""C:\\models\\a.obj""#);
+        assert_eq!(json_string("tab\tnew\nline"), r#""tab\u0009new\u000aline""#);
+        assert_eq!(json_string("\u{1f}\u{20}"), r#""\u001f ""#);
+        assert_eq!(json_string("é"), "\"é\"");
+    }
+
+    #[test]
+    fn json_report_has_every_field() {
+        let report = BenchReport {
+            options: BenchOptions {
+                model: PathBuf::from("model.obj"),
+                frames: 2,
+                width: 30,
+                height: 20,
+            },
+            min: Duration::from_micros(1500),
+            mean: Duration::from_millis(2),
+            p99: Duration::from_micros(2250),
+            stats: RenderStats {
+                triangles_submitted: 10,
+                triangles_culled: 4,
+                triangles_clipped: 1,
+                triangles_rasterized: 5,
+                pixels_shaded: 300,
+                ..RenderStats::default()
+            },
+        };
+
+        assert_eq!(
+            report.to_json(),
+            r#"{
+  "model": "model.obj",
+  "frames": 2,
+  "width": 30,
+  "height": 20,
+  "frame_time_ms": { "min": 1.5000, "mean": 2.0000, "p99": 2.2500 },
+  "triangles_submitted": 10,
+  "triangles_culled": 4,
+  "triangles_clipped": 1,
+  "triangles_rasterized": 5,
+  "pixels_shaded": 300
+}
+"#
+        );
+    }
+}
@@ -1,56 +1,101 @@
-use crate::primitives::vertex::Vertex;
+use crate::primitives::{
+    matrix::Mat4,
+    vector::{Vec3, Vec4},
+    vertex::Vertex,
+};
 
 #[derive(Debug, Clone)]
 pub struct Camera {
-    pub position: Vertex,
+    pub position: Vec3,
+    /// Pitch, yaw and roll in radians.
     pub rotation: (f32, f32, f32),
     pub fov: f64,
     pub near: f64,
     pub far: f64,
     pub screen_width: usize,
     pub screen_height: usize,
+    /// Sub-pixel offset added to every projected position, e.g. for
+    /// temporal anti-aliasing.
+    pub jitter: (f64, f64),
 }
 
 impl Camera {
     pub fn new(screen_width: usize, screen_height: usize) -> Self {
         Self {
-            position: Vertex::new(0.0, 0.0, 0.0, 0.0, 0.0),
+            position: Vec3::default(),
             rotation: (0.0, 0.0, 0.0),
             fov: 90.0,
             near: 0.1,
             far: 1000.0,
-            screen_width: screen_width,
-            screen_height: screen_height,
+            screen_width,
+            screen_height,
+            jitter: (0.0, 0.0),
         }
     }
 
-    pub fn world_to_view(&self, world: &Vertex) -> Vertex {
-        let mut x = world.x - self.position.x;
-        let mut y = world.y - self.position.y;
-        let mut z = world.z - self.position.z;
-
-        let pitch = self.rotation.0;
-        let yaw = self.rotation.1;
+    /// Sets pitch and yaw so the camera faces `target`. Roll is cleared.
+    pub fn look_at(&mut self, target: Vec3) {
+        let r = target - self.position;
+        let yaw = (-r.x).atan2(r.z);
+        let forward = -yaw.sin() * r.x + yaw.cos() * r.z;
+        let pitch = r.y.atan2(forward);
 
-        let cosy = yaw.cos() as f64;
-        let siny = yaw.sin() as f64;
+        self.rotation = (pitch as f32, yaw as f32, 0.0);
+    }
 
-        let xz = cosy * x + siny * z;
-        let zz = -siny * x + cosy * z;
+    /// World to view space: translate to the camera, then apply yaw, pitch
+    /// and roll.
+    pub fn view_matrix(&self) -> Mat4 {
+        Mat4::rotation_z(self.rotation.2 as f64)
+            * Mat4::rotation_x(self.rotation.0 as f64)
+            * Mat4::rotation_y(self.rotation.1 as f64)
+            * Mat4::translation(-self.position)
+    }
 
-        x = xz;
-        z = zz;
+    pub fn projection_matrix(&self) -> Mat4 {
+        let aspect = self.screen_width as f64 / self.screen_height as f64;
+        Mat4::perspective(self.fov.to_radians(), aspect, self.near, self.far)
+    }
 
-        let cosp = pitch.cos() as f64;
-        let sinp = pitch.sin() as f64;
+    pub fn world_to_view(&self, world: &Vertex) -> Vertex {
+        let v = self
+            .view_matrix()
+            .transform_point(Vec3::new(world.x, world.y, world.z));
 
-        let yz = cosp * y - sinp * z;
-        let zz = sinp * y + cosp * z;
+        Vertex::new(v.x, v.y, v.z, world.u, world.v)
+    }
 
-        y = yz;
-        z = zz;
+    /// Projects a view-space vertex to screen space, keeping view-space `z`
+    /// for depth and perspective-correct interpolation. The vertex must
+    /// already lie in front of the camera.
+    pub fn view_to_screen(&self, projection: &Mat4, v: &Vertex) -> Vertex {
+        let clip = *projection * Vec4::new(v.x, v.y, v.z, 1.0);
+        let x = clip.x / clip.w;
+        let y = clip.y / clip.w;
+
+        Vertex::new(
+            x * self.screen_width as f64 * 0.5 + self.screen_width as f64 * 0.5 + self.jitter.0,
+            -y * self.screen_height as f64 * 0.5 + self.screen_height as f64 * 0.5 + self.jitter.1,
+            v.z,
+            v.u,
+            v.v,
+        )
+    }
 
-        Vertex::new(x, y, z, world.u, world.v)
+    /// The view-space point at screen position `x`, `y` and view depth
+    /// `depth`: the inverse of [`Camera::view_to_screen`] for a
+    /// perspective `projection`.
+    pub fn screen_to_view(&self, projection: &Mat4, x: f64, y: f64, depth: f64) -> Vec3 {
+        let half_width = self.screen_width as f64 * 0.5;
+        let half_height = self.screen_height as f64 * 0.5;
+        let ndc_x = (x - self.jitter.0 - half_width) / half_width;
+        let ndc_y = -(y - self.jitter.1 - half_height) / half_height;
+
+        Vec3::new(
+            ndc_x * depth / projection.rows[0][0],
+            ndc_y * depth / projection.rows[1][1],
+            depth,
+        )
     }
 
     pub fn project_perspective(&self, world: &Vertex) -> Option<Vertex> {
@@ -60,18 +105,54 @@ impl Camera {
             return None;
         }
 
-        let aspect = self.screen_width as f64 / self.screen_height as f64;
-        let f = 1.0 / (self.fov.to_radians() * 0.5).tan();
+        Some(self.view_to_screen(&self.projection_matrix(), &v))
+    }
+}
 
-        let x = (v.x * f / aspect) / v.z;
-        let y = (v.y * f) / v.z;
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
 
-        Some(Vertex::new(
-            x * self.screen_width as f64 * 0.5 + self.screen_width as f64 * 0.5,
-            -y * self.screen_height as f64 * 0.5 + self.screen_height as f64 * 0.5,
-            v.z,
-            v.u,
-            v.v,
-        ))
+    use super::*;
+
+    fn project(camera: &Camera, x: f64, y: f64, z: f64) -> (f64, f64) {
+        let p = camera
+            .project_perspective(&Vertex::new(x, y, z, 0.0, 0.0))
+            .unwrap();
+        (p.x, p.y)
+    }
+
+    fn assert_close(a: (f64, f64), b: (f64, f64)) {
+        assert!(
+            (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn roll_turns_the_image_about_its_centre() {
+        let mut camera = Camera::new(200, 200);
+        assert_close(project(&camera, 1.0, 0.0, 2.0), (150.0, 100.0));
+
+        camera.rotation.2 = FRAC_PI_2;
+        assert_close(project(&camera, 1.0, 0.0, 2.0), (100.0, 50.0));
+        assert_close(project(&camera, 0.0, 0.0, 2.0), (100.0, 100.0));
+    }
+
+    #[test]
+    fn roll_is_applied_after_pitch_and_yaw() {
+        let mut camera = Camera::new(200, 200);
+        camera.position = Vec3::new(0.0, 0.0, -4.0);
+        camera.rotation = (0.3, 0.5, 0.0);
+        let ahead = camera
+            .view_matrix()
+            .inverse()
+            .unwrap()
+            .transform_point(Vec3::new(0.0, 0.0, 5.0));
+        assert_close(project(&camera, ahead.x, ahead.y, ahead.z), (100.0, 100.0));
+
+        // Roll turns the view about the direction pitch and yaw point in.
+        camera.rotation.2 = 1.0;
+        assert_close(project(&camera, ahead.x, ahead.y, ahead.z), (100.0, 100.0));
     }
 }
@@ -0,0 +1,498 @@
+use std::path::PathBuf;
+
+use software_renderer::{
+    bench::BenchOptions,
+    camera::Camera,
+    hdr::ToneMapper,
+    headless::HeadlessOptions,
+    msaa::Msaa,
+    post::AntiAliasing,
+    primitives::{colour::Colour, quaternion::Quat, texture::Filter, vector::Vec3},
+    state::{CullMode, DepthMode, FrontFace},
+};
+
+pub const USAGE: &str = "\
+Usage:
+  software_renderer [VIEW OPTIONS] [MODEL[@X,Y,Z]]...
+  software_renderer render <MODEL> <PNG> [VIEW OPTIONS] [RENDER OPTIONS]
+  software_renderer bench <MODEL> [BENCH OPTIONS]
+
+Opens a window showing each MODEL at its position (default 0,0,10).
+With no models, assets/objects/skull.obj is shown.
+
+View options:
+  --size <W>x<H>                 window or image size in pixels (default 600x600)
+  --fov <DEGREES>                vertical field of view, 1-179 (default 90)
+  --near <DISTANCE>              near clip plane, above 0 (default 0.1)
+  --far <DISTANCE>               far clip plane, beyond near (default 1000)
+  --camera <X>,<Y>,<Z>           camera position (default 0,0,0)
+  --camera-rotation <P>,<Y>,<R>  camera pitch, yaw, roll in degrees (default 0,0,0)
+  --scale <N>                    internal resolution relative to the size, e.g. 0.5 or 2
+                                 (default 1)
+
+Render options:
+  --position <X>,<Y>,<Z>         model position (default 0,0,10)
+  --model-rotation <X>,<Y>,<Z>   model rotation in degrees (default 0,0,0)
+  --background <R>,<G>,<B>[,<A>] clear colour, 0-255 (default 0,0,0)
+  --cull <back|front|none>       faces to cull (default back)
+  --front-face <cw|ccw>          on-screen winding of front faces (default cw)
+  --depth <MODE>                 depth buffer encoding: linear or reversed-z
+                                 (default linear; reversed-z has no far plane)
+  --hdr <TONE MAPPER>            render in HDR and tone map with reinhard, aces, filmic
+                                 or clamp
+  --exposure <N>                 HDR exposure multiplier (default 1)
+  --filter <nearest|bilinear>    texture sampling (default nearest)
+  --msaa <1|2|4|8>               samples per pixel for anti-aliasing (default 1)
+  --aa <off|fxaa|taa>            screen-space anti-aliasing (default off)
+
+Bench options:
+  --frames <N>                   frames to render along the camera orbit (default 240)
+  --size <W>x<H>                 frame size in pixels (default 600x600)
+  --json <PATH>                  also write the results as JSON";
+
+#[cfg(feature = "window")]
+const DEFAULT_MODEL: &str = "assets/objects/skull.obj";
+#[cfg(feature = "window")]
+const DEFAULT_MODEL_POSITION: Vec3 = Vec3::new(0.0, 0.0, 10.0);
+
+/// Screen size and camera settings shared by every command.
+#[derive(Debug, Clone)]
+pub struct ViewOptions {
+    pub width: usize,
+    pub height: usize,
+    pub fov: f64,
+    pub near: f64,
+    pub far: f64,
+    pub camera_position: Vec3,
+    pub camera_rotation: (f32, f32, f32),
+    pub scale: f64,
+}
+
+impl Default for ViewOptions {
+    fn default() -> Self {
+        let camera = Camera::new(600, 600);
+
+        Self {
+            width: camera.screen_width,
+            height: camera.screen_height,
+            fov: camera.fov,
+            near: camera.near,
+            far: camera.far,
+            camera_position: camera.position,
+            camera_rotation: camera.rotation,
+            scale: 1.0,
+        }
+    }
+}
+
+impl ViewOptions {
+    #[cfg(feature = "window")]
+    pub fn camera(&self) -> Camera {
+        Camera {
+            position: self.camera_position,
+            rotation: self.camera_rotation,
+            fov: self.fov,
+            near: self.near,
+            far: self.far,
+            ..Camera::new(self.width, self.height)
+        }
+    }
+
+    /// Applies `option` if it is a view option. Returns false otherwise.
+    fn apply(&mut self, option: &str, value: &str) -> Result<bool, String> {
+        match option {
+            "--size" => (self.width, self.height) = parse_size(value)?,
+            "--fov" => self.fov = parse_number(option, value)?,
+            "--near" => self.near = parse_number(option, value)?,
+            "--far" => self.far = parse_number(option, value)?,
+            "--camera" => self.camera_position = parse_vec3(option, value)?,
+            "--camera-rotation" => {
+                let r = parse_vec3(option, value)?;
+                self.camera_rotation = (
+                    r.x.to_radians() as f32,
+                    r.y.to_radians() as f32,
+                    r.z.to_radians() as f32,
+                );
+            }
+            "--scale" => self.scale = parse_number(option, value)?,
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !(1.0..=179.0).contains(&self.fov) {
+            return Err(format!(
+                "`--fov` must be between 1 and 179, got {}",
+                self.fov
+            ));
+        }
+        if !(self.scale > 0.0 && self.scale <= 4.0) {
+            return Err(format!(
+                "`--scale` must be above 0 and at most 4, got {}",
+                self.scale
+            ));
+        }
+        if self.near <= 0.0 {
+            return Err(format!("`--near` must be above 0, got {}", self.near));
+        }
+        if self.far <= self.near {
+            return Err(format!(
+                "`--far` ({}) must be beyond `--near` ({})",
+                self.far, self.near
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+pub enum Command {
+    Help,
+    #[cfg(feature = "window")]
+    Window {
+        view: ViewOptions,
+        models: Vec<(PathBuf, Vec3)>,
+    },
+    Render {
+        options: HeadlessOptions,
+        output: PathBuf,
+    },
+    Bench {
+        options: BenchOptions,
+        json: Option<PathBuf>,
+    },
+}
+
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        return Ok(Command::Help);
+    }
+
+    match args.first().map(String::as_str) {
+        Some("render") => parse_render(&args[1..]),
+        Some("bench") => parse_bench(&args[1..]),
+        _ => parse_window(args),
+    }
+}
+
+#[cfg(feature = "window")]
+fn parse_window(args: &[String]) -> Result<Command, String> {
+    let mut view = ViewOptions::default();
+    let mut models = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            let value = option_value(arg, args.next())?;
+            if !view.apply(arg, value)? {
+                return Err(format!("unknown option `{arg}`"));
+            }
+        } else {
+            models.push(parse_model(arg));
+        }
+    }
+
+    view.validate()?;
+
+    if models.is_empty() {
+        models.push((PathBuf::from(DEFAULT_MODEL), DEFAULT_MODEL_POSITION));
+    }
+
+    Ok(Command::Window { view, models })
+}
+
+#[cfg(not(feature = "window"))]
+fn parse_window(args: &[String]) -> Result<Command, String> {
+    match args.first() {
+        Some(arg) => Err(format!(
+            "unknown command `{arg}`; this build has no window (`window` feature disabled)"
+        )),
+        None => Err(String::from(
+            "no command given, and this build has no window (`window` feature disabled)",
+        )),
+    }
+}
+
+fn parse_render(args: &[String]) -> Result<Command, String> {
+    let mut view = ViewOptions::default();
+    let mut options = HeadlessOptions::default();
+    let mut positional: Vec<&str> = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            positional.push(arg);
+            continue;
+        }
+
+        let value = option_value(arg, args.next())?;
+
+        if view.apply(arg, value)? {
+            continue;
+        }
+
+        match arg.as_str() {
+            "--position" => options.model_position = parse_vec3(arg, value)?,
+            "--model-rotation" => {
+                let r = parse_vec3(arg, value)?;
+                options.model_rotation =
+                    Quat::from_euler(r.x.to_radians(), r.y.to_radians(), r.z.to_radians());
+            }
+            "--background" => options.background = parse_colour(value)?,
+            "--cull" => {
+                options.cull_mode = match value {
+                    "back" => CullMode::Back,
+                    "front" => CullMode::Front,
+                    "none" => CullMode::None,
+                    _ => {
+                        return Err(format!(
+                            "invalid cull mode `{value}`, expected back, front or none"
+                        ));
+                    }
+                }
+            }
+            "--depth" => {
+                options.depth_mode = match value {
+                    "linear" => DepthMode::Linear,
+                    "reversed-z" => DepthMode::ReversedZ,
+                    _ => {
+                        return Err(format!(
+                            "invalid depth mode `{value}`, expected linear or reversed-z"
+                        ));
+                    }
+                }
+            }
+            "--hdr" => {
+                options.hdr = Some(match value {
+                    "reinhard" => ToneMapper::Reinhard,
+                    "aces" => ToneMapper::Aces,
+                    "filmic" => ToneMapper::Filmic,
+                    "clamp" => ToneMapper::Clamp,
+                    _ => {
+                        return Err(format!(
+                            "invalid tone mapper `{value}`, expected reinhard, aces, filmic or clamp"
+                        ));
+                    }
+                })
+            }
+            "--exposure" => {
+                options.exposure = parse_number(arg, value)
+                    .ok()
+                    .filter(|&n| n > 0.0)
+                    .ok_or_else(|| format!("`--exposure` must be above 0, got `{value}`"))?
+                    as f32
+            }
+            "--filter" => {
+                options.filter = match value {
+                    "nearest" => Filter::Nearest,
+                    "bilinear" => Filter::Bilinear,
+                    _ => {
+                        return Err(format!(
+                            "invalid filter `{value}`, expected nearest or bilinear"
+                        ));
+                    }
+                }
+            }
+            "--msaa" => {
+                options.msaa = match value {
+                    "1" => Msaa::Off,
+                    "2" => Msaa::X2,
+                    "4" => Msaa::X4,
+                    "8" => Msaa::X8,
+                    _ => {
+                        return Err(format!(
+                            "invalid sample count `{value}`, expected 1, 2, 4 or 8"
+                        ));
+                    }
+                }
+            }
+            "--aa" => {
+                options.anti_aliasing = match value {
+                    "off" => AntiAliasing::Off,
+                    "fxaa" => AntiAliasing::Fxaa,
+                    "taa" => AntiAliasing::Taa,
+                    _ => {
+                        return Err(format!(
+                            "invalid anti-aliasing `{value}`, expected off, fxaa or taa"
+                        ));
+                    }
+                }
+            }
+            "--front-face" => {
+                options.front_face = match value {
+                    "cw" => FrontFace::Clockwise,
+                    "ccw" => FrontFace::CounterClockwise,
+                    _ => {
+                        return Err(format!("invalid front face `{value}`, expected cw or ccw"));
+                    }
+                }
+            }
+            _ => return Err(format!("unknown option `{arg}`")),
+        }
+    }
+
+    view.validate()?;
+
+    let [model, output] = positional[..] else {
+        return Err(String::from(
+            "`render` takes a model path and an output path",
+        ));
+    };
+
+    options.model = PathBuf::from(model);
+    options.width = view.width;
+    options.height = view.height;
+    options.fov = view.fov;
+    options.near = view.near;
+    options.far = view.far;
+    options.camera_position = view.camera_position;
+    options.camera_rotation = view.camera_rotation;
+    options.scale = view.scale;
+
+    Ok(Command::Render {
+        options,
+        output: PathBuf::from(output),
+    })
+}
+
+fn parse_bench(args: &[String]) -> Result<Command, String> {
+    let mut options = BenchOptions::default();
+    let mut json = None;
+    let mut positional: Vec<&str> = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            positional.push(arg);
+            continue;
+        }
+
+        let value = option_value(arg, args.next())?;
+
+        match arg.as_str() {
+            "--frames" => {
+                options.frames =
+                    value.parse().ok().filter(|&n| n > 0).ok_or_else(|| {
+                        format!("invalid frame count `{value}`, expected at least 1")
+                    })?;
+            }
+            "--size" => (options.width, options.height) = parse_size(value)?,
+            "--json" => json = Some(PathBuf::from(value)),
+            _ => return Err(format!("unknown option `{arg}`")),
+        }
+    }
+
+    let [model] = positional[..] else {
+        return Err(String::from("`bench` takes one model path"));
+    };
+
+    options.model = PathBuf::from(model);
+
+    Ok(Command::Bench { options, json })
+}
+
+fn option_value<'a>(option: &str, value: Option<&'a String>) -> Result<&'a str, String> {
+    value
+        .map(String::as_str)
+        .ok_or_else(|| format!("`{option}` needs a value"))
+}
+
+/// Parses `path` or `path@x,y,z`. A suffix that is not a position is part
+/// of the path, so file names containing `@` still load.
+#[cfg(feature = "window")]
+fn parse_model(arg: &str) -> (PathBuf, Vec3) {
+    if let Some((path, position)) = arg.rsplit_once('@')
+        && let Ok(position) = parse_vec3(path, position)
+    {
+        return (PathBuf::from(path), position);
+    }
+
+    (PathBuf::from(arg), DEFAULT_MODEL_POSITION)
+}
+
+fn parse_size(value: &str) -> Result<(usize, usize), String> {
+    let error = || format!("invalid size `{value}`, expected <W>x<H> such as 800x600");
+
+    let (w, h) = value.split_once('x').ok_or_else(error)?;
+    let w: usize = w.parse().map_err(|_| error())?;
+    let h: usize = h.parse().map_err(|_| error())?;
+
+    if w == 0 || h == 0 {
+        return Err(error());
+    }
+
+    Ok((w, h))
+}
+
+fn parse_number(option: &str, value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite())
+        .ok_or_else(|| format!("invalid value `{value}` for `{option}`, expected a number"))
+}
+
+fn parse_vec3(option: &str, value: &str) -> Result<Vec3, String> {
+    let error = || format!("invalid value `{value}` for `{option}`, expected <X>,<Y>,<Z>");
+
+    let parts = value
+        .split(',')
+        .map(|p| p.trim().parse::<f64>().map_err(|_| error()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let [x, y, z] = parts[..] else {
+        return Err(error());
+    };
+
+    Ok(Vec3::new(x, y, z))
+}
+
+fn parse_colour(value: &str) -> Result<Colour, String> {
+    let error =
+        || format!("invalid colour `{value}`, expected <R>,<G>,<B> or <R>,<G>,<B>,<A> in 0-255");
+
+    let parts = value
+        .split(',')
+        .map(|p| p.trim().parse::<u8>().map_err(|_| error()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match parts[..] {
+        [r, g, b] => Ok(Colour::new(r, g, b, 255)),
+        [r, g, b, a] => Ok(Colour::new(r, g, b, a)),
+        _ => Err(error()),
+    }
+}
+
+#[cfg(all(test, feature = "window"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn model_position_follows_the_last_at_sign() {
+        assert_eq!(
+            parse_model("models/ship.obj@1,-2,3.5"),
+            (PathBuf::from("models/ship.obj"), Vec3::new(1.0, -2.0, 3.5))
+        );
+        assert_eq!(
+            parse_model("me@home/ship.obj@0,0,4"),
+            (PathBuf::from("me@home/ship.obj"), Vec3::new(0.0, 0.0, 4.0))
+        );
+    }
+
+    #[test]
+    fn model_without_a_position_keeps_the_whole_path() {
+        for arg in [
+            "ship.obj",
+            "me@home/ship.obj",
+            "ship@2x.obj",
+            "ship.obj@1,2",
+        ] {
+            assert_eq!(
+                parse_model(arg),
+                (PathBuf::from(arg), DEFAULT_MODEL_POSITION)
+            );
+        }
+    }
+}
@@ -0,0 +1,105 @@
+/// Curve that compresses linear HDR colour into 0-1 for display.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToneMapper {
+    /// Cuts everything above 1 off, as the 8-bit path does.
+    Clamp,
+    /// `x / (1 + x)`.
+    #[default]
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve.
+    Aces,
+    /// Hable's curve from Uncharted 2.
+    Filmic,
+}
+
+impl ToneMapper {
+    /// Maps one linear channel, already scaled by exposure, to 0-1.
+    pub fn map(self, x: f32) -> f32 {
+        let x = x.max(0.0);
+
+        match self {
+            Self::Clamp => x.min(1.0),
+            Self::Reinhard => x / (1.0 + x),
+            Self::Aces => ((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)).min(1.0),
+            Self::Filmic => {
+                const WHITE: f32 = 11.2;
+                const EXPOSURE_BIAS: f32 = 2.0;
+                (hable(x * EXPOSURE_BIAS) / hable(WHITE)).min(1.0)
+            }
+        }
+    }
+
+    /// The mapper after this one, wrapping around, for cycling with a key.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Clamp => Self::Reinhard,
+            Self::Reinhard => Self::Aces,
+            Self::Aces => Self::Filmic,
+            Self::Filmic => Self::Clamp,
+        }
+    }
+}
+
+fn hable(x: f32) -> f32 {
+    const A: f32 = 0.15;
+    const B: f32 = 0.50;
+    const C: f32 = 0.10;
+    const D: f32 = 0.20;
+    const E: f32 = 0.02;
+    const F: f32 = 0.30;
+
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAPPERS: [ToneMapper; 4] = [
+        ToneMapper::Clamp,
+        ToneMapper::Reinhard,
+        ToneMapper::Aces,
+        ToneMapper::Filmic,
+    ];
+
+    #[test]
+    fn every_mapper_is_monotonic_from_black_into_0_to_1() {
+        for mapper in MAPPERS {
+            assert!(mapper.map(0.0).abs() < 1e-6, "{mapper:?}");
+            assert_eq!(mapper.map(-3.0), mapper.map(0.0), "{mapper:?}");
+
+            let mut previous = mapper.map(0.0);
+            for i in 1..=2000 {
+                let mapped = mapper.map(i as f32 * 0.05);
+                assert!((0.0..=1.0).contains(&mapped), "{mapper:?}");
+                assert!(
+                    mapped >= previous,
+                    "{mapper:?} falls at {}",
+                    i as f32 * 0.05
+                );
+                previous = mapped;
+            }
+        }
+    }
+
+    #[test]
+    fn known_values() {
+        assert_eq!(ToneMapper::Clamp.map(0.25), 0.25);
+        assert_eq!(ToneMapper::Clamp.map(4.0), 1.0);
+        assert_eq!(ToneMapper::Reinhard.map(1.0), 0.5);
+        assert_eq!(ToneMapper::Reinhard.map(3.0), 0.75);
+        assert_eq!(ToneMapper::Aces.map(1000.0), 1.0);
+        assert!((ToneMapper::Aces.map(0.18) - 0.2666).abs() < 1e-3);
+        // Filmic reaches white at the white point, before exposure bias.
+        assert!((ToneMapper::Filmic.map(11.2 / 2.0) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn next_visits_every_mapper() {
+        let mut mapper = ToneMapper::Clamp;
+        for expected in MAPPERS.iter().cycle().skip(1).take(4) {
+            mapper = mapper.next();
+            assert_eq!(mapper, *expected);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::path::{Path, PathBuf};
+
+use image::{ColorType, save_buffer};
+
+use crate::{
+    camera::Camera,
+    hdr::ToneMapper,
+    loader::load_wavefront,
+    msaa::Msaa,
+    post::{AntiAliasing, Fxaa, PostChain},
+    primitives::{colour::Colour, quaternion::Quat, texture::Filter, vector::Vec3},
+    renderer::Renderer,
+    resolution::{RenderScale, resample},
+    scene::{Instance, NodeContent, Scene},
+    state::{CullMode, DepthMode, DepthState, FrontFace},
+    taa::Taa,
+    transform::Transform,
+};
+
+/// Frames accumulated for a still image with temporal anti-aliasing.
+const TAA_FRAMES: usize = 16;
+
+/// A single windowless frame of one model.
+#[derive(Debug, Clone)]
+pub struct HeadlessOptions {
+    pub model: PathBuf,
+    pub width: usize,
+    pub height: usize,
+    /// Vertical field of view in degrees.
+    pub fov: f64,
+    pub near: f64,
+    pub far: f64,
+    pub model_position: Vec3,
+    pub model_rotation: Quat,
+    pub camera_position: Vec3,
+    pub camera_rotation: (f32, f32, f32),
+    pub background: Colour,
+    pub cull_mode: CullMode,
+    pub front_face: FrontFace,
+    pub depth_mode: DepthMode,
+    /// Render into an HDR buffer and resolve it with this tone mapper.
+    pub hdr: Option<ToneMapper>,
+    pub exposure: f32,
+    /// Sampling for every texture of the model.
+    pub filter: Filter,
+    pub msaa: Msaa,
+    /// With TAA, the frame is drawn repeatedly until the jitter has
+    /// converged.
+    pub anti_aliasing: AntiAliasing,
+    /// Internal resolution relative to `width` and `height`. The image is
+    /// resampled to the requested size when saved.
+    pub scale: f64,
+}
+
+impl Default for HeadlessOptions {
+    fn default() -> Self {
+        Self {
+            model: PathBuf::new(),
+            width: 600,
+            height: 600,
+            fov: 90.0,
+            near: 0.1,
+            far: 1000.0,
+            model_position: Vec3::new(0.0, 0.0, 10.0),
+            model_rotation: Quat::IDENTITY,
+            camera_position: Vec3::default(),
+            camera_rotation: (0.0, 0.0, 0.0),
+            background: Colour::new(0, 0, 0, 255),
+            cull_mode: CullMode::default(),
+            front_face: FrontFace::default(),
+            depth_mode: DepthMode::default(),
+            hdr: None,
+            exposure: 1.0,
+            filter: Filter::default(),
+            msaa: Msaa::default(),
+            anti_aliasing: AntiAliasing::default(),
+            scale: 1.0,
+        }
+    }
+}
+
+/// Loads the model and renders one frame, returning the renderer so the
+/// caller can inspect `buffer` and `depth`. These are at the internal
+/// resolution given by `scale`.
+pub fn render(options: &HeadlessOptions) -> Result<Renderer, String> {
+    let mut mesh = load_wavefront(&options.model)?;
+    for texture in mesh.materials.iter_mut().flat_map(|m| m.textures_mut()) {
+        texture.filter = options.filter;
+    }
+
+    let mut scene = Scene::new();
+    let mesh = scene.add_mesh(mesh);
+    scene.add(
+        "model",
+        Transform {
+            translation: options.model_position,
+            rotation: options.model_rotation,
+            ..Transform::default()
+        },
+        NodeContent::Mesh(Instance::new(mesh)),
+    );
+
+    let (width, height) = RenderScale::fixed(options.scale).size(options.width, options.height);
+    let mut camera = Camera::new(width, height);
+    camera.position = options.camera_position;
+    camera.rotation = options.camera_rotation;
+    camera.fov = options.fov;
+    camera.near = options.near;
+    camera.far = options.far;
+
+    let mut renderer = Renderer::new(width, height);
+    renderer.cull_mode = options.cull_mode;
+    renderer.front_face = options.front_face;
+    renderer.depth_state = DepthState::with_mode(options.depth_mode);
+    renderer.set_msaa(options.msaa);
+    if let Some(tone_mapper) = options.hdr {
+        renderer.set_hdr(true);
+        renderer.tone_mapper = tone_mapper;
+        renderer.exposure = options.exposure;
+    }
+
+    let taa = options.anti_aliasing == AntiAliasing::Taa;
+    let mut history = Taa::new();
+
+    for _ in 0..if taa { TAA_FRAMES } else { 1 } {
+        if taa {
+            history.jitter(&mut camera);
+        }
+
+        renderer.clear(options.background);
+        renderer.draw_scene(&scene, &camera);
+        renderer.resolve();
+
+        if taa {
+            history.resolve(&mut renderer, &camera);
+        }
+    }
+
+    if options.anti_aliasing == AntiAliasing::Fxaa {
+        PostChain::new().push(Fxaa::default()).apply(&mut renderer);
+    }
+
+    Ok(renderer)
+}
+
+/// Renders one frame and returns its RGBA pixels at the requested `width`
+/// and `height`, resampled from the internal resolution if `scale` is not 1.
+pub fn render_rgba(options: &HeadlessOptions) -> Result<Vec<u8>, String> {
+    let renderer = render(options)?;
+
+    let size = (options.width, options.height);
+    if (renderer.width, renderer.height) == size {
+        return Ok(renderer.buffer);
+    }
+
+    let mut rgba = vec![0; size.0 * size.1 * 4];
+    resample(
+        &renderer.buffer,
+        (renderer.width, renderer.height),
+        &mut rgba,
+        size,
+    );
+    Ok(rgba)
+}
+
+pub fn render_to_png(options: &HeadlessOptions, output: &Path) -> Result<(), String> {
+    let rgba = render_rgba(options)?;
+    write_png(&rgba, (options.width, options.height), output)
+}
+
+/// Writes the renderer's colour buffer as an RGBA PNG.
+pub fn save_png(renderer: &Renderer, path: &Path) -> Result<(), String> {
+    write_png(&renderer.buffer, (renderer.width, renderer.height), path)
+}
+
+fn write_png(rgba: &[u8], (width, height): (usize, usize), path: &Path) -> Result<(), String> {
+    save_buffer(path, rgba, width as u32, height as u32, ColorType::Rgba8)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cube(scale: f64) -> HeadlessOptions {
+        HeadlessOptions {
+            model: Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/objects/cube.obj"),
+            width: 30,
+            height: 20,
+            model_position: Vec3::new(0.0, 0.0, 20.0),
+            scale,
+            ..HeadlessOptions::default()
+        }
+    }
+
+    #[test]
+    fn scaled_frames_are_returned_at_the_requested_size() {
+        for scale in [0.5, 1.0, 2.0] {
+            let options = cube(scale);
+
+            let renderer = render(&options).unwrap();
+            let expected = RenderScale::fixed(scale).size(30, 20);
+            assert_eq!((renderer.width, renderer.height), expected);
+            assert_eq!(renderer.buffer.len(), expected.0 * expected.1 * 4);
+
+            assert_eq!(render_rgba(&options).unwrap().len(), 30 * 20 * 4);
+        }
+    }
+
+    #[test]
+    fn unscaled_frames_are_the_renderer_buffer() {
+        let options = cube(1.0);
+
+        assert_eq!(
+            render_rgba(&options).unwrap(),
+            render(&options).unwrap().buffer
+        );
+    }
+}
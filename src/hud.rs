@@ -0,0 +1,254 @@
+use std::{collections::VecDeque, time::Duration};
+
+use crate::{
+    camera::Camera,
+    font::{GLYPH_HEIGHT, text_size},
+    primitives::colour::Colour,
+    renderer::Renderer,
+    scene::{NodeContent, Scene},
+};
+
+/// Frame times kept for the graph, one pixel column each.
+const HISTORY: usize = 120;
+
+/// Frame time at the top of the graph.
+const GRAPH_MAX_MS: f64 = 50.0;
+const GRAPH_HEIGHT: usize = 40;
+
+const MARGIN: i32 = 4;
+const PADDING: i32 = 4;
+
+/// On-screen text overlay with frame rate, camera, scene and pipeline
+/// statistics. Draw it after the 3D pass so it sits on top.
+#[derive(Debug, Clone, Default)]
+pub struct Hud {
+    pub visible: bool,
+    frame_times: VecDeque<Duration>,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the duration of a finished frame to the history.
+    pub fn record_frame(&mut self, frame_time: Duration) {
+        if self.frame_times.len() == HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+    }
+
+    /// Mean frame time over the history.
+    pub fn average_frame_time(&self) -> Duration {
+        if self.frame_times.is_empty() {
+            return Duration::ZERO;
+        }
+
+        self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32
+    }
+
+    /// Draws the overlay over `renderer`'s buffer, using the stats of the
+    /// frame drawn so far.
+    pub fn draw(&self, renderer: &mut Renderer, camera: &Camera, scene: &Scene) {
+        let text = self.text(renderer, camera, scene);
+        self.draw_text(renderer, &text);
+    }
+
+    /// Draws the overlay with `text` from [`Hud::text`] over `renderer`,
+    /// which need not be the one the text describes, e.g. when the frame was
+    /// drawn at another resolution.
+    pub fn draw_text(&self, renderer: &mut Renderer, text: &str) {
+        if !self.visible {
+            return;
+        }
+
+        let (text_width, text_height) = text_size(text);
+        let width = text_width.max(HISTORY) as i32 + 2 * PADDING;
+        let height = (text_height + GLYPH_HEIGHT + GRAPH_HEIGHT) as i32 + 2 * PADDING;
+
+        renderer.fill_rect(
+            MARGIN,
+            MARGIN,
+            width as usize,
+            height as usize,
+            Colour::new(0, 0, 0, 160),
+        );
+
+        let left = MARGIN + PADDING;
+        let top = MARGIN + PADDING;
+        renderer.draw_text(left, top, text, Colour::new(255, 255, 255, 255));
+
+        let graph_bottom = top + height - 2 * PADDING - 1;
+        self.draw_graph(renderer, left, graph_bottom);
+    }
+
+    /// The overlay's lines for the frame `renderer` has drawn so far.
+    pub fn text(&self, renderer: &Renderer, camera: &Camera, scene: &Scene) -> String {
+        let frame_ms = self.average_frame_time().as_secs_f64() * 1000.0;
+        let fps = if frame_ms > 0.0 {
+            1000.0 / frame_ms
+        } else {
+            0.0
+        };
+        let p = camera.position;
+        let stats = &renderer.stats;
+
+        let mut models: Vec<String> = Vec::new();
+        scene.traverse(|_, node, _| {
+            if let NodeContent::Mesh(_) = node.content {
+                models.push(node.name.clone());
+            }
+        });
+
+        let mut text = format!(
+            "{fps:.1} fps ({frame_ms:.2} ms) at {}x{}\n\
+             camera {:.2} {:.2} {:.2}\n\
+             models {}\n\
+             mode {:?}, cull {:?}, front {:?}\n\
+             depth {:?}, hdr {}, msaa {}x\n\
+             tris {} sub {} cull {} clip {} drawn\n\
+             frags {} tested {} depth {} written",
+            renderer.width,
+            renderer.height,
+            p.x,
+            p.y,
+            p.z,
+            if models.is_empty() {
+                String::from("none")
+            } else {
+                models.join(", ")
+            },
+            renderer.polygon_mode,
+            renderer.cull_mode,
+            renderer.front_face,
+            renderer.depth_state.mode,
+            match renderer.hdr {
+                Some(_) => format!("{:?} x{:.2}", renderer.tone_mapper, renderer.exposure),
+                None => String::from("off"),
+            },
+            renderer.msaa().samples(),
+            stats.triangles_submitted,
+            stats.triangles_culled,
+            stats.triangles_clipped,
+            stats.triangles_rasterized,
+            stats.fragments_tested,
+            stats.fragments_passed_depth,
+            stats.fragments_written,
+        );
+
+        if renderer.profile_stages {
+            let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+            let t = &stats.timings;
+            text += &format!(
+                "\nvtx {:.2} setup {:.2} raster {:.2} ms",
+                ms(t.vertex),
+                ms(t.setup),
+                ms(t.raster),
+            );
+        }
+
+        text
+    }
+
+    /// Plots the frame time history as a line graph above `bottom`, with a
+    /// marker at 60 fps.
+    fn draw_graph(&self, renderer: &mut Renderer, left: i32, bottom: i32) {
+        let y_for = |d: Duration| {
+            let ms = d.as_secs_f64() * 1000.0;
+            bottom - ((ms / GRAPH_MAX_MS).min(1.0) * GRAPH_HEIGHT as f64) as i32
+        };
+
+        let target = y_for(Duration::from_secs_f64(1.0 / 60.0));
+        renderer.draw_line_2d(
+            left,
+            target,
+            left + HISTORY as i32 - 1,
+            target,
+            Colour::new(255, 80, 80, 160),
+        );
+
+        let points: Vec<(i32, i32)> = self
+            .frame_times
+            .iter()
+            .enumerate()
+            .map(|(i, &d)| (left + i as i32, y_for(d)))
+            .collect();
+
+        for pair in points.windows(2) {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            renderer.draw_line_2d(x0, y0, x1, y1, Colour::new(120, 255, 120, 255));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn renderer() -> Renderer {
+        let mut renderer = Renderer::new(200, 160);
+        renderer.clear(Colour::new(255, 255, 255, 255));
+        renderer
+    }
+
+    #[test]
+    fn history_keeps_the_latest_frames() {
+        let mut hud = Hud::new();
+        assert_eq!(hud.average_frame_time(), Duration::ZERO);
+
+        for ms in 0..HISTORY as u64 + 10 {
+            hud.record_frame(Duration::from_millis(ms));
+        }
+
+        // Frames 10 to 129 remain.
+        assert_eq!(hud.frame_times.len(), HISTORY);
+        assert_eq!(hud.average_frame_time(), Duration::from_micros(69_500));
+    }
+
+    #[test]
+    fn hidden_hud_draws_nothing() {
+        let mut renderer = renderer();
+        let before = renderer.buffer.clone();
+
+        Hud::new().draw_text(&mut renderer, "hello");
+        assert_eq!(renderer.buffer, before);
+    }
+
+    #[test]
+    fn visible_hud_darkens_a_panel_and_writes_on_it() {
+        let mut renderer = renderer();
+        let hud = Hud {
+            visible: true,
+            ..Hud::new()
+        };
+        hud.draw_text(&mut renderer, "#");
+
+        let red = |x: usize, y: usize| renderer.buffer[(y * renderer.width + x) * 4];
+        // Outside the margin, on the panel, and inside the '#' glyph.
+        assert_eq!(red(0, 0), 255);
+        assert!(red(MARGIN as usize + 1, MARGIN as usize + 1) < 255);
+        assert_eq!(
+            red((MARGIN + PADDING) as usize + 1, (MARGIN + PADDING) as usize),
+            255
+        );
+        assert_eq!(red(199, 159), 255);
+    }
+
+    #[test]
+    fn text_reports_the_frame_counters() {
+        let mut renderer = renderer();
+        renderer.stats.triangles_submitted = 12;
+        renderer.stats.fragments_written = 34;
+
+        let text = Hud::new().text(&renderer, &Camera::new(200, 160), &Scene::new());
+        assert!(text.contains("models none"), "{text}");
+        assert!(text.contains("tris 12 sub"), "{text}");
+        assert!(text.contains("34 written"), "{text}");
+        assert!(!text.contains("vtx"), "{text}");
+
+        renderer.profile_stages = true;
+        let text = Hud::new().text(&renderer, &Camera::new(200, 160), &Scene::new());
+        assert!(text.contains("vtx"), "{text}");
+    }
+}
@@ -0,0 +1,24 @@
+#[cfg(feature = "window")]
+pub mod app;
+pub mod bench;
+pub mod camera;
+pub mod font;
+pub mod hdr;
+pub mod headless;
+pub mod hud;
+pub mod light;
+pub mod loader;
+pub mod math;
+pub mod mesh;
+pub mod msaa;
+pub mod post;
+pub mod primitives;
+pub mod renderer;
+pub mod resolution;
+pub mod scene;
+pub mod state;
+pub mod stats;
+pub mod taa;
+pub mod target;
+pub mod transform;
+pub mod watcher;
@@ -0,0 +1,34 @@
+use crate::primitives::colour::Colour;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    /// Shines along the owning node's +Z axis from infinitely far away.
+    Directional,
+    /// Radiates from the owning node's origin, fading out at `range`.
+    Point { range: f64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    pub kind: LightKind,
+    pub colour: Colour,
+    pub intensity: f64,
+}
+
+impl Light {
+    pub fn directional(colour: Colour, intensity: f64) -> Self {
+        Self {
+            kind: LightKind::Directional,
+            colour,
+            intensity,
+        }
+    }
+
+    pub fn point(colour: Colour, intensity: f64, range: f64) -> Self {
+        Self {
+            kind: LightKind::Point { range },
+            colour,
+            intensity,
+        }
+    }
+}
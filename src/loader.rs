@@ -1,7 +1,9 @@
 use std::{
     collections::HashMap,
-    fs::read_to_string,
+    fs::{read, read_to_string},
     path::{Path, PathBuf},
+    str::from_utf8,
+    thread,
 };
 
 use crate::{
@@ -9,21 +11,110 @@ use crate::{
     primitives::{
         colour::Colour,
         material::Material,
-        texture::Texture,
+        texture::{ColourSpace, Texture},
         triangle::Triangle,
         vector::{Vec2, Vec3},
         vertex::Vertex,
     },
+    transform::Transform,
 };
 
+/// Files smaller than this are parsed on the calling thread; larger files
+/// are split into roughly this many bytes per worker.
+const MIN_CHUNK_BYTES: usize = 1 << 20;
+
+/// A face corner with zero-based `(v, vt, vn)` indices into the whole file.
+type FaceKey = (usize, usize, usize);
+
+/// An index as written in a chunk. Negative OBJ indices count back from the
+/// last element defined so far, which a chunk only knows relative to its own
+/// start, so they are resolved during the merge.
+#[derive(Debug, Clone, Copy)]
+enum Index {
+    Absolute(usize),
+    /// Zero-based index relative to the chunk's first element, negative
+    /// when it points into an earlier chunk.
+    Local(isize),
+}
+
+impl Index {
+    /// The index into the whole file, given how many elements of its kind
+    /// came before the chunk.
+    fn resolve(self, offset: usize) -> Result<usize, &'static str> {
+        match self {
+            Self::Absolute(i) => Ok(i),
+            Self::Local(i) => offset.checked_add_signed(i).ok_or("Invalid index"),
+        }
+    }
+}
+
+/// A state-changing statement, recorded with the number of faces that
+/// preceded it in its chunk so the merge can replay it in file order.
+enum Directive<'a> {
+    Mtllib(&'a str),
+    Usemtl(&'a str),
+}
+
+/// Everything parsed from one line-aligned slice of the OBJ. Strings borrow
+/// from the file buffer, so parsing never allocates per line.
+#[derive(Default)]
+struct Chunk<'a> {
+    positions: Vec<Vec3>,
+    uvs: Vec<Vec2>,
+    /// Normals are validated but not stored; nothing downstream reads them.
+    normal_count: usize,
+    faces: Vec<[[Index; 3]; 3]>,
+    directives: Vec<(usize, Directive<'a>)>,
+    error: Option<String>,
+}
+
 pub fn load_wavefront(path: &Path) -> Result<Mesh, String> {
-    let mut positions: Vec<Vec3> = Vec::new();
-    let mut uvs: Vec<Vec2> = Vec::new();
-    let mut normals: Vec<Vec3> = Vec::new();
+    let bytes = read(path).map_err(|_| "Failed to read OBJ")?;
+    let source = from_utf8(&bytes).map_err(|_| "Failed to read OBJ")?;
+
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+    parse_obj(
+        path,
+        source,
+        (source.len() / MIN_CHUNK_BYTES).clamp(1, workers),
+    )
+}
+
+/// Parses OBJ `source`, read from `path`, in up to `chunk_count` parallel
+/// chunks. The result does not depend on the chunk count.
+fn parse_obj(path: &Path, source: &str, chunk_count: usize) -> Result<Mesh, String> {
+    // ---- OBJ parsing ----
+    let slices = split_lines(source, chunk_count);
+
+    let chunks: Vec<Chunk> = if slices.len() == 1 {
+        vec![parse_chunk(slices[0])]
+    } else {
+        thread::scope(|scope| {
+            let handles: Vec<_> = slices
+                .iter()
+                .map(|slice| scope.spawn(move || parse_chunk(slice)))
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("OBJ parser thread panicked"))
+                .collect()
+        })
+    };
+
+    let mut positions: Vec<Vec3> =
+        Vec::with_capacity(chunks.iter().map(|c| c.positions.len()).sum());
+    let mut uvs: Vec<Vec2> = Vec::with_capacity(chunks.iter().map(|c| c.uvs.len()).sum());
+    let face_count: usize = chunks.iter().map(|c| c.faces.len()).sum();
+
+    for chunk in &chunks {
+        positions.extend_from_slice(&chunk.positions);
+        uvs.extend_from_slice(&chunk.uvs);
+    }
 
     let mut vertices: Vec<Vertex> = Vec::new();
-    let mut triangles: Vec<Triangle> = Vec::new();
-    let mut vertex_map: HashMap<(usize, usize, usize), usize> = HashMap::new();
+    let mut triangles: Vec<Triangle> = Vec::with_capacity(face_count);
+    let mut vertex_map: HashMap<FaceKey, usize> = HashMap::new();
 
     // ---- Materials ----
     let mut materials: Vec<Material> = Vec::new();
@@ -34,139 +125,245 @@ pub fn load_wavefront(path: &Path) -> Result<Mesh, String> {
         name: "__default".to_string(),
         diffuse: Colour::new(255, 255, 255, 255),
         kd_texture: None,
+        normal_texture: None,
+        bump_texture: None,
+        roughness_texture: None,
+        emissive: Vec3::default(),
+        double_sided: false,
     });
     material_lookup.insert("__default".to_string(), 0);
 
     let mut current_material_index: usize = 0;
 
-    // ---- OBJ parsing ----
-    for line in read_to_string(path)
-        .map_err(|_| "Failed to read OBJ")?
-        .lines()
-    {
-        let words: Vec<&str> = line.split_whitespace().collect();
-        if words.is_empty() || words[0].starts_with('#') {
-            continue;
-        }
+    let mut sources: Vec<PathBuf> = vec![path.to_path_buf()];
 
-        match words[0] {
-            "v" => {
-                if words.len() != 4 {
-                    return Err(String::from("Invalid vertex"));
-                }
-                positions.push(Vec3 {
-                    x: words[1].parse().map_err(|_| "Invalid vertex")?,
-                    y: words[2].parse().map_err(|_| "Invalid vertex")?,
-                    z: words[3].parse().map_err(|_| "Invalid vertex")?,
-                });
-            }
+    // Elements defined before the chunk being merged, for resolving
+    // relative indices.
+    let (mut position_offset, mut uv_offset, mut normal_offset) = (0, 0, 0);
 
-            "vt" => {
-                if words.len() < 3 {
-                    return Err(String::from("Invalid vt"));
-                }
-                let u: f64 = words[1].parse().map_err(|_| "Invalid vt")?;
-                let v: f64 = words[2].parse().map_err(|_| "Invalid vt")?;
-                uvs.push(Vec2 { x: u, y: 1.0 - v });
-            }
+    // ---- Merge, in file order ----
+    for chunk in &chunks {
+        let mut directives = chunk.directives.iter().peekable();
 
-            "vn" => {
-                if words.len() != 4 {
-                    return Err(String::from("Invalid vn"));
-                }
-                normals.push(Vec3 {
-                    x: words[1].parse().map_err(|_| "Invalid vn")?,
-                    y: words[2].parse().map_err(|_| "Invalid vn")?,
-                    z: words[3].parse().map_err(|_| "Invalid vn")?,
-                });
-            }
+        for face_index in 0..=chunk.faces.len() {
+            while let Some((_, directive)) = directives.next_if(|(at, _)| *at == face_index) {
+                match directive {
+                    Directive::Mtllib(file) => {
+                        let parent = path.parent().ok_or("Invalid OBJ path")?;
+                        let mtl_path = parent.join(file);
 
-            "f" => {
-                if words.len() != 4 {
-                    return Err(String::from("Faces must be triangulated"));
-                }
+                        let loaded = load_materials(mtl_path.clone())?;
+                        sources.push(mtl_path);
 
-                let mut face_indices = [0usize; 3];
+                        for material in loaded {
+                            sources.extend(material.textures().filter_map(|t| t.path.clone()));
 
-                for i in 0..3 {
-                    let parts: Vec<&str> = words[i + 1].split('/').collect();
-                    if parts.len() != 3 {
-                        return Err(String::from("Faces must be v/vt/vn"));
+                            let index = materials.len();
+                            material_lookup.insert(material.name.clone(), index);
+                            materials.push(material);
+                        }
                     }
 
-                    let pi = parts[0].parse::<usize>().map_err(|_| "Invalid index")? - 1;
-                    let ti = parts[1].parse::<usize>().map_err(|_| "Invalid index")? - 1;
-                    let ni = parts[2].parse::<usize>().map_err(|_| "Invalid index")? - 1;
-
-                    let key = (pi, ti, ni);
-
-                    let index = if let Some(&idx) = vertex_map.get(&key) {
-                        idx
-                    } else {
-                        let p = positions[pi];
-                        let uv = uvs[ti];
-                        let v = Vertex::new(p.x, p.y, p.z, uv.x, uv.y);
-                        let idx = vertices.len();
-                        vertices.push(v);
-                        vertex_map.insert(key, idx);
-                        idx
-                    };
-
-                    face_indices[i] = index;
+                    Directive::Usemtl(name) => {
+                        current_material_index =
+                            *material_lookup.get(*name).ok_or("Material not found")?;
+                    }
                 }
-
-                triangles.push(Triangle::new(
-                    face_indices[0],
-                    face_indices[1],
-                    face_indices[2],
-                    current_material_index,
-                ));
             }
 
-            "mtllib" => {
-                if words.len() != 2 {
-                    return Err(String::from("Invalid mtllib"));
-                }
+            let Some(face) = chunk.faces.get(face_index) else {
+                break;
+            };
 
-                let parent = path.parent().ok_or("Invalid OBJ path")?;
-                let mtl_path = parent.join(words[1]);
+            let mut face_indices = [0usize; 3];
 
-                let loaded = load_materials(mtl_path)?;
+            for (i, &[p, t, n]) in face.iter().enumerate() {
+                let key = (
+                    p.resolve(position_offset)?,
+                    t.resolve(uv_offset)?,
+                    n.resolve(normal_offset)?,
+                );
 
-                for material in loaded {
-                    let index = materials.len();
-                    material_lookup.insert(material.name.clone(), index);
-                    materials.push(material);
-                }
+                face_indices[i] = if let Some(&idx) = vertex_map.get(&key) {
+                    idx
+                } else {
+                    let (pi, ti, _) = key;
+                    let p = positions.get(pi).ok_or("Invalid index")?;
+                    let uv = uvs.get(ti).ok_or("Invalid index")?;
+                    let idx = vertices.len();
+                    vertices.push(Vertex::new(p.x, p.y, p.z, uv.x, uv.y));
+                    vertex_map.insert(key, idx);
+                    idx
+                };
             }
 
-            "usemtl" => {
-                if words.len() != 2 {
-                    return Err(String::from("Invalid usemtl"));
-                }
-
-                current_material_index =
-                    *material_lookup.get(words[1]).ok_or("Material not found")?;
-            }
+            triangles.push(Triangle::new(
+                face_indices[0],
+                face_indices[1],
+                face_indices[2],
+                current_material_index,
+            ));
+        }
 
-            _ => {}
+        if let Some(error) = &chunk.error {
+            return Err(error.clone());
         }
+
+        position_offset += chunk.positions.len();
+        uv_offset += chunk.uvs.len();
+        normal_offset += chunk.normal_count;
     }
 
     Ok(Mesh {
         vertices,
         triangles,
-        centre: Vec3 {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-        },
-        rotate_around_pivot: false,
-        pivot: None,
+        transform: Transform::default(),
         materials,
+        sources,
     })
 }
 
+/// Splits `source` into at most `count` slices that each end on a line break.
+fn split_lines(source: &str, count: usize) -> Vec<&str> {
+    let bytes = source.as_bytes();
+    let mut slices = Vec::with_capacity(count);
+    let mut start = 0;
+
+    for i in 1..=count {
+        let mut end = (source.len() * i / count).max(start);
+
+        if i < count {
+            end = match bytes[end..].iter().position(|&b| b == b'\n') {
+                Some(offset) => end + offset + 1,
+                None => source.len(),
+            };
+        }
+
+        if end > start {
+            slices.push(&source[start..end]);
+            start = end;
+        }
+    }
+
+    if slices.is_empty() {
+        slices.push(source);
+    }
+
+    slices
+}
+
+/// Parses one slice of the OBJ. Parsing stops at the first malformed line,
+/// keeping what came before it so the merge reports errors in file order.
+fn parse_chunk(source: &str) -> Chunk<'_> {
+    let mut chunk = Chunk::default();
+
+    for line in source.lines() {
+        if let Err(error) = parse_line(line, &mut chunk) {
+            chunk.error = Some(error.to_string());
+            break;
+        }
+    }
+
+    chunk
+}
+
+fn parse_line<'a>(line: &'a str, chunk: &mut Chunk<'a>) -> Result<(), &'static str> {
+    let mut words = line.split_whitespace();
+    let Some(keyword) = words.next() else {
+        return Ok(());
+    };
+    if keyword.starts_with('#') {
+        return Ok(());
+    }
+
+    match keyword {
+        "v" => {
+            let [x, y, z] = exact(words).ok_or("Invalid vertex")?;
+            chunk.positions.push(Vec3 {
+                x: x.parse().map_err(|_| "Invalid vertex")?,
+                y: y.parse().map_err(|_| "Invalid vertex")?,
+                z: z.parse().map_err(|_| "Invalid vertex")?,
+            });
+        }
+
+        "vt" => {
+            let (Some(u), Some(v)) = (words.next(), words.next()) else {
+                return Err("Invalid vt");
+            };
+            let u: f64 = u.parse().map_err(|_| "Invalid vt")?;
+            let v: f64 = v.parse().map_err(|_| "Invalid vt")?;
+            chunk.uvs.push(Vec2 { x: u, y: 1.0 - v });
+        }
+
+        "vn" => {
+            let [x, y, z] = exact(words).ok_or("Invalid vn")?;
+            for n in [x, y, z] {
+                n.parse::<f64>().map_err(|_| "Invalid vn")?;
+            }
+            chunk.normal_count += 1;
+        }
+
+        "f" => {
+            let corners: [&str; 3] = exact(words).ok_or("Faces must be triangulated")?;
+            let mut face = [[Index::Absolute(0); 3]; 3];
+
+            for (i, corner) in corners.iter().enumerate() {
+                let mut parts = corner.split('/');
+                let (Some(p), Some(t), Some(n), None) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    return Err("Faces must be v/vt/vn");
+                };
+
+                face[i] = [
+                    parse_index(p, chunk.positions.len())?,
+                    parse_index(t, chunk.uvs.len())?,
+                    parse_index(n, chunk.normal_count)?,
+                ];
+            }
+
+            chunk.faces.push(face);
+        }
+
+        "mtllib" => {
+            let [file] = exact(words).ok_or("Invalid mtllib")?;
+            chunk
+                .directives
+                .push((chunk.faces.len(), Directive::Mtllib(file)));
+        }
+
+        "usemtl" => {
+            let [name] = exact(words).ok_or("Invalid usemtl")?;
+            chunk
+                .directives
+                .push((chunk.faces.len(), Directive::Usemtl(name)));
+        }
+
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Takes exactly `N` remaining words, or `None` if there are more or fewer.
+fn exact<'a, const N: usize>(mut words: impl Iterator<Item = &'a str>) -> Option<[&'a str; N]> {
+    let mut out = [""; N];
+    for slot in out.iter_mut() {
+        *slot = words.next()?;
+    }
+    words.next().is_none().then_some(out)
+}
+
+/// Parses a one-based OBJ index into a zero-based one. Negative indices
+/// count back from the end of the `defined` elements the chunk has so far.
+fn parse_index(word: &str, defined: usize) -> Result<Index, &'static str> {
+    match word.parse::<isize>().map_err(|_| "Invalid index")? {
+        0 => Err("Invalid index"),
+        i if i > 0 => Ok(Index::Absolute(i as usize - 1)),
+        i => Ok(Index::Local(defined as isize + i)),
+    }
+}
+
 pub fn load_materials(path: PathBuf) -> Result<Vec<Material>, String> {
     let mut materials: Vec<Material> = Vec::new();
     let mut current: Option<Material> = None;
@@ -196,6 +393,11 @@ pub fn load_materials(path: PathBuf) -> Result<Vec<Material>, String> {
                     name: words[1].to_string(),
                     diffuse: Colour::new(255, 255, 255, 255),
                     kd_texture: None,
+                    normal_texture: None,
+                    bump_texture: None,
+                    roughness_texture: None,
+                    emissive: Vec3::default(),
+                    double_sided: false,
                 });
             }
 
@@ -213,17 +415,52 @@ pub fn load_materials(path: PathBuf) -> Result<Vec<Material>, String> {
                 );
             }
 
+            "Ke" => {
+                let mat = current.as_mut().ok_or("Ke before newmtl")?;
+                let [r, g, b] = exact(words[1..].iter().copied()).ok_or("Invalid Ke")?;
+
+                mat.emissive = Vec3::new(
+                    r.parse().map_err(|_| "Invalid Ke")?,
+                    g.parse().map_err(|_| "Invalid Ke")?,
+                    b.parse().map_err(|_| "Invalid Ke")?,
+                );
+            }
+
             "d" => {
                 let mat = current.as_mut().ok_or("d before newmtl")?;
                 let a: f64 = words[1].parse().map_err(|_| "Invalid d")?;
                 mat.diffuse.alpha = (a * 255.0).round() as u8;
             }
 
+            // Not part of the MTL spec, which has no way to mark a material
+            // as double-sided; documented in the README.
+            "double_sided" => {
+                let mat = current.as_mut().ok_or("double_sided before newmtl")?;
+                mat.double_sided = match words.get(1) {
+                    None | Some(&"1") | Some(&"on") => true,
+                    Some(&"0") | Some(&"off") => false,
+                    _ => return Err(String::from("Invalid double_sided")),
+                };
+            }
+
             "map_Kd" => {
                 let mat = current.as_mut().ok_or("map_Kd before newmtl")?;
-                let tex_path = base_dir.join(words[1]);
-                mat.kd_texture =
-                    Some(Texture::from_file(&tex_path).map_err(|_| "Invalid texture")?);
+                mat.kd_texture = Some(load_map(&base_dir, &words, ColourSpace::Srgb)?);
+            }
+
+            "norm" => {
+                let mat = current.as_mut().ok_or("norm before newmtl")?;
+                mat.normal_texture = Some(load_map(&base_dir, &words, ColourSpace::Linear)?);
+            }
+
+            "map_Bump" | "bump" => {
+                let mat = current.as_mut().ok_or("Bump map before newmtl")?;
+                mat.bump_texture = Some(load_map(&base_dir, &words, ColourSpace::Linear)?);
+            }
+
+            "map_Pr" => {
+                let mat = current.as_mut().ok_or("map_Pr before newmtl")?;
+                mat.roughness_texture = Some(load_map(&base_dir, &words, ColourSpace::Linear)?);
             }
 
             _ => {}
@@ -236,3 +473,174 @@ pub fn load_materials(path: PathBuf) -> Result<Vec<Material>, String> {
 
     Ok(materials)
 }
+
+/// Loads the texture named by a `map_*` statement. The file name comes last,
+/// after any options such as `-bm 1.0`.
+fn load_map(base_dir: &Path, words: &[&str], colour_space: ColourSpace) -> Result<Texture, String> {
+    let file = words
+        .last()
+        .filter(|_| words.len() >= 2)
+        .ok_or("Missing texture file")?;
+    Texture::from_file(&base_dir.join(file), colour_space)
+        .map_err(|_| String::from("Invalid texture"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Elements interleaved with faces using absolute and negative indices,
+    /// ending with faces that reach back across every chunk boundary.
+    fn source() -> String {
+        let mut source = String::new();
+
+        for i in 0..40 {
+            let f = i as f64;
+            source += &format!("v {f} {} {}\n", f * 0.5, -f);
+            source += &format!("vt {} {}\n", f / 40.0, 1.0 - f / 40.0);
+            source += "vn 0 0 1\n";
+
+            if i >= 2 {
+                source += "f -1/-1/-1 -2/-2/-2 -3/-3/-3\n";
+                source += &format!("f {}/{}/1 1/1/1 {i}/{i}/1\n", i + 1, i + 1);
+            }
+            if i % 7 == 0 {
+                source += "usemtl __default\n";
+            }
+        }
+
+        source + "f -40/-40/-40 -20/-20/-20 -1/-1/-1\n"
+    }
+
+    #[test]
+    fn chunked_parse_matches_serial() {
+        let source = source();
+        let path = Path::new("test.obj");
+        let serial = format!("{:?}", parse_obj(path, &source, 1).unwrap());
+
+        for chunks in 2..=8 {
+            assert_eq!(split_lines(&source, chunks).len(), chunks);
+
+            let parallel = format!("{:?}", parse_obj(path, &source, chunks).unwrap());
+            assert_eq!(parallel, serial, "{chunks} chunks");
+        }
+    }
+
+    #[test]
+    fn negative_indices_count_back_from_the_last_element() {
+        let elements = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 1\nvn 0 0 1\n";
+        let path = Path::new("test.obj");
+
+        let relative = parse_obj(
+            path,
+            &format!("{elements}f -3/-2/-1 -2/-1/-1 -1/-2/-1\n"),
+            1,
+        );
+        let absolute = parse_obj(path, &format!("{elements}f 1/1/1 2/2/1 3/1/1\n"), 1);
+
+        assert_eq!(format!("{relative:?}"), format!("{absolute:?}"));
+    }
+
+    #[test]
+    fn invalid_indices_are_rejected() {
+        let elements = "v 0 0 0\nvt 0 0\nvn 0 0 1\n";
+        let path = Path::new("test.obj");
+
+        for face in [
+            "f 0/1/1 1/1/1 1/1/1",
+            "f -2/1/1 1/1/1 1/1/1",
+            "f 2/1/1 1/1/1 1/1/1",
+        ] {
+            assert!(
+                parse_obj(path, &format!("{elements}{face}\n"), 1).is_err(),
+                "{face}"
+            );
+        }
+    }
+
+    /// A directory of its own for each test, so tests running in parallel,
+    /// or in other processes, never share files.
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "software_renderer_loader_{name}_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Loads `source` as an MTL file from [`test_dir`], which is then
+    /// removed along with anything else the test put there.
+    fn materials_from(name: &str, source: &str) -> Result<Vec<Material>, String> {
+        let dir = test_dir(name);
+        let path = dir.join("materials.mtl");
+        std::fs::write(&path, source).unwrap();
+        let result = load_materials(path);
+        std::fs::remove_dir_all(dir).unwrap();
+        result
+    }
+
+    #[test]
+    fn emissive_colour_is_read_from_ke() {
+        let materials = materials_from("ke", "newmtl glow\nKe 2 0.5 0\n").unwrap();
+
+        assert_eq!(materials[0].emissive, Vec3::new(2.0, 0.5, 0.0));
+    }
+
+    #[test]
+    fn malformed_ke_is_an_error() {
+        for line in ["Ke", "Ke 1 1", "Ke 1 1 1 1", "Ke 1 x 1"] {
+            let result = materials_from("bad_ke", &format!("newmtl glow\n{line}\n"));
+            assert_eq!(result.unwrap_err(), "Invalid Ke", "{line}");
+        }
+    }
+
+    #[test]
+    fn normal_and_bump_maps_fill_their_own_slots() {
+        let dir = test_dir("maps");
+        for (name, value) in [("normal", 128), ("bump", 40)] {
+            image::RgbaImage::from_pixel(1, 1, image::Rgba([value, value, 255, 255]))
+                .save(dir.join(format!("{name}.png")))
+                .unwrap();
+        }
+
+        let materials = materials_from(
+            "maps",
+            "newmtl a\nnorm normal.png\nbump -bm 0.5 bump.png\nnewmtl b\nmap_Bump bump.png\n",
+        )
+        .unwrap();
+
+        let texel = |t: &Option<Texture>| t.as_ref().map(|t| (t.data[0].red, t.colour_space));
+        assert_eq!(
+            texel(&materials[0].normal_texture),
+            Some((128, ColourSpace::Linear))
+        );
+        assert_eq!(
+            texel(&materials[0].bump_texture),
+            Some((40, ColourSpace::Linear))
+        );
+        assert_eq!(texel(&materials[1].normal_texture), None);
+        assert_eq!(
+            texel(&materials[1].bump_texture),
+            Some((40, ColourSpace::Linear))
+        );
+    }
+
+    #[test]
+    fn double_sided_takes_an_optional_switch() {
+        for (line, expected) in [
+            ("", false),
+            ("double_sided", true),
+            ("double_sided 1", true),
+            ("double_sided on", true),
+            ("double_sided 0", false),
+            ("double_sided off", false),
+        ] {
+            let materials = materials_from("double_sided", &format!("newmtl leaf\n{line}\n"));
+            assert_eq!(materials.unwrap()[0].double_sided, expected, "{line}");
+        }
+
+        let result = materials_from("bad_double_sided", "newmtl leaf\ndouble_sided yes\n");
+        assert_eq!(result.unwrap_err(), "Invalid double_sided");
+    }
+}
@@ -1,19 +1,73 @@
-use winit::event_loop::EventLoop;
+use std::{env, fs, process::exit};
 
-mod app;
-mod camera;
-mod loader;
-mod math;
-mod mesh;
-mod primitives;
-mod renderer;
+#[cfg(feature = "window")]
+use software_renderer::primitives::vector::Vec3;
+use software_renderer::{bench, headless::render_to_png};
 
-use app::App;
+mod cli;
+
+use cli::Command;
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let command = match cli::parse(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("error: {e}\n\n{}", cli::USAGE);
+            exit(2);
+        }
+    };
+
+    match command {
+        Command::Help => println!("{}", cli::USAGE),
+        #[cfg(feature = "window")]
+        Command::Window { view, models } => run_window(view, models),
+        Command::Render { options, output } => {
+            if let Err(e) = render_to_png(&options, &output) {
+                eprintln!("error: {e}");
+                exit(1);
+            }
+        }
+        Command::Bench { options, json } => {
+            let report = match bench::run(&options) {
+                Ok(report) => report,
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    exit(1);
+                }
+            };
+
+            println!("{report}");
+
+            if let Some(path) = json
+                && let Err(e) = fs::write(&path, report.to_json())
+            {
+                eprintln!("error: failed to write `{}`: {e}", path.display());
+                exit(1);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "window")]
+fn run_window(view: cli::ViewOptions, models: Vec<(std::path::PathBuf, Vec3)>) {
+    use software_renderer::{app::App, resolution::RenderScale, transform::Transform};
+    use winit::event_loop::EventLoop;
+
+    let mut app = App::new(view.width, view.height);
+    app.set_camera(view.camera());
+    app.set_render_scale(RenderScale::fixed(view.scale));
+
+    for (path, position) in models {
+        if let Err(e) = app.add_model(&path, Transform::from_translation(position)) {
+            eprintln!("error: failed to load `{}`: {e}", path.display());
+            exit(1);
+        }
+    }
+
     let event_loop = EventLoop::new().unwrap();
     event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
 
-    let mut app = App::new(600, 600);
     event_loop.run_app(&mut app).unwrap();
 }
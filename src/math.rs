@@ -1,80 +1,227 @@
-use crate::primitives::{vector::Vec3, vertex::Vertex};
-
-pub fn rotate_x(vertex: &Vertex, angle: f32) -> Vertex {
-    let sin = angle.sin() as f64;
-    let cos = angle.cos() as f64;
-
-    Vertex::new(
-        vertex.x,
-        vertex.y * cos - vertex.z * sin,
-        vertex.y * sin + vertex.z * cos,
-        vertex.u,
-        vertex.v,
-    )
-}
+use crate::primitives::{matrix::Mat4, vector::Vec3, vertex::Vertex};
 
-pub fn rotate_y(vertex: &Vertex, angle: f32) -> Vertex {
-    let sin = angle.sin() as f64;
-    let cos = angle.cos() as f64;
-
-    Vertex::new(
-        vertex.x * cos + vertex.z * sin,
-        vertex.y,
-        -vertex.x * sin + vertex.z * cos,
-        vertex.u,
-        vertex.v,
-    )
-}
+pub fn transform_vertex(m: &Mat4, vertex: &Vertex) -> Vertex {
+    let p = m.transform_point(Vec3::new(vertex.x, vertex.y, vertex.z));
 
-pub fn rotate_z(vertex: &Vertex, angle: f32) -> Vertex {
-    let sin = angle.sin() as f64;
-    let cos = angle.cos() as f64;
-
-    Vertex::new(
-        vertex.x * cos - vertex.y * sin,
-        vertex.x * sin + vertex.y * cos,
-        vertex.z,
-        vertex.u,
-        vertex.v,
-    )
+    Vertex::new(p.x, p.y, p.z, vertex.u, vertex.v)
 }
 
-pub fn rotate_vertex(vertex: &Vertex, rotation: (f32, f32, f32)) -> Vertex {
-    let mut rotated = *vertex;
-
-    rotated = rotate_x(&rotated, rotation.0);
-    rotated = rotate_y(&rotated, rotation.1);
-    rotated = rotate_z(&rotated, rotation.2);
-
-    rotated
-}
+/// Most vertices a triangle can have after clipping against two planes.
+pub const MAX_CLIPPED_VERTICES: usize = 5;
 
-pub fn rotate_around_pivot(vertex: &Vertex, pivot: &Vec3, rotation: (f32, f32, f32)) -> Vertex {
-    let mut v = Vertex::new(
-        vertex.x - pivot.x,
-        vertex.y - pivot.y,
-        vertex.z - pivot.z,
-        vertex.u,
-        vertex.v,
-    );
+/// Clips a view-space triangle to `near <= z <= far`, returning the
+/// resulting convex polygon and its vertex count (0 if fully outside).
+pub fn clip_triangle_depth(
+    triangle: [Vertex; 3],
+    near: f64,
+    far: f64,
+) -> ([Vertex; MAX_CLIPPED_VERTICES], usize) {
+    let mut polygon = [triangle[0]; MAX_CLIPPED_VERTICES];
+    polygon[..3].copy_from_slice(&triangle);
 
-    v = rotate_vertex(&v, rotation);
+    let count = clip_polygon(&mut polygon, 3, |v| v.z - near);
+    let count = clip_polygon(&mut polygon, count, |v| far - v.z);
 
-    Vertex::new(v.x + pivot.x, v.y + pivot.y, v.z + pivot.z, v.u, v.v)
+    (polygon, count)
 }
 
-pub fn is_back_facing(v0: &Vertex, v1: &Vertex, v2: &Vertex) -> bool {
-    let ax = v1.x - v0.x;
-    let ay = v1.y - v0.y;
-    let az = v1.z - v0.z;
-
-    let bx = v2.x - v0.x;
-    let by = v2.y - v0.y;
-    let bz = v2.z - v0.z;
+/// Clips a view-space line segment to `near <= z <= far`. Returns `None`
+/// if no part of it is inside.
+pub fn clip_line_depth(a: Vertex, b: Vertex, near: f64, far: f64) -> Option<(Vertex, Vertex)> {
+    let mut t0: f64 = 0.0;
+    let mut t1: f64 = 1.0;
+
+    for (da, db) in [(a.z - near, b.z - near), (far - a.z, far - b.z)] {
+        if da < 0.0 && db < 0.0 {
+            return None;
+        }
+
+        if da < 0.0 {
+            t0 = t0.max(da / (da - db));
+        } else if db < 0.0 {
+            t1 = t1.min(da / (da - db));
+        }
+    }
+
+    (t0 <= t1).then(|| (a.lerp(&b, t0), a.lerp(&b, t1)))
+}
 
-    let _nx = ay * bz - az * by;
-    let _ny = az * bx - ax * bz;
-    let nz = ax * by - ay * bx;
+/// Sutherland-Hodgman against one plane, keeping vertices where
+/// `distance >= 0`. Works in place; returns the new vertex count.
+fn clip_polygon(
+    polygon: &mut [Vertex; MAX_CLIPPED_VERTICES],
+    count: usize,
+    distance: impl Fn(&Vertex) -> f64,
+) -> usize {
+    if count == 0 || polygon[..count].iter().all(|v| distance(v) >= 0.0) {
+        return count;
+    }
+
+    let input = *polygon;
+    let mut out = 0;
+
+    for i in 0..count {
+        let a = &input[i];
+        let b = &input[(i + 1) % count];
+        let da = distance(a);
+        let db = distance(b);
+
+        if da >= 0.0 {
+            polygon[out] = *a;
+            out += 1;
+        }
+
+        if (da >= 0.0) != (db >= 0.0) && out < MAX_CLIPPED_VERTICES {
+            polygon[out] = a.lerp(b, da / (da - db));
+            out += 1;
+        }
+    }
+
+    out
+}
 
-    nz <= 0.0
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEAR: f64 = 1.0;
+    const FAR: f64 = 10.0;
+
+    fn vertex(x: f64, y: f64, z: f64) -> Vertex {
+        Vertex::new(x, y, z, x, y)
+    }
+
+    fn clip(triangle: [Vertex; 3]) -> Vec<Vertex> {
+        let (polygon, count) = clip_triangle_depth(triangle, NEAR, FAR);
+        polygon[..count].to_vec()
+    }
+
+    fn assert_within_depth(polygon: &[Vertex]) {
+        for v in polygon {
+            assert!(
+                (NEAR - 1e-9..=FAR + 1e-9).contains(&v.z),
+                "{v:?} is outside"
+            );
+        }
+    }
+
+    #[test]
+    fn triangle_inside_is_unchanged() {
+        let triangle = [
+            vertex(0.0, 0.0, 2.0),
+            vertex(1.0, 0.0, 3.0),
+            vertex(0.0, 1.0, 4.0),
+        ];
+        let polygon = clip(triangle);
+
+        assert_eq!(polygon.len(), 3);
+        for (a, b) in polygon.iter().zip(&triangle) {
+            assert_eq!((a.x, a.y, a.z), (b.x, b.y, b.z));
+        }
+    }
+
+    #[test]
+    fn triangle_outside_is_dropped() {
+        let behind = [
+            vertex(0.0, 0.0, 0.0),
+            vertex(1.0, 0.0, 0.5),
+            vertex(0.0, 1.0, -2.0),
+        ];
+        let beyond = [
+            vertex(0.0, 0.0, 11.0),
+            vertex(1.0, 0.0, 12.0),
+            vertex(0.0, 1.0, 20.0),
+        ];
+
+        assert!(clip(behind).is_empty());
+        assert!(clip(beyond).is_empty());
+    }
+
+    #[test]
+    fn one_vertex_behind_the_near_plane_leaves_a_quad() {
+        let polygon = clip([
+            vertex(0.0, 0.0, 0.0),
+            vertex(1.0, 0.0, 3.0),
+            vertex(0.0, 1.0, 3.0),
+        ]);
+
+        assert_eq!(polygon.len(), 4);
+        assert_within_depth(&polygon);
+        assert_eq!(polygon.iter().filter(|v| v.z == NEAR).count(), 2);
+    }
+
+    #[test]
+    fn two_vertices_behind_the_near_plane_leave_a_triangle() {
+        let polygon = clip([
+            vertex(0.0, 0.0, 0.0),
+            vertex(1.0, 0.0, 0.0),
+            vertex(0.0, 1.0, 3.0),
+        ]);
+
+        assert_eq!(polygon.len(), 3);
+        assert_within_depth(&polygon);
+    }
+
+    #[test]
+    fn triangle_across_both_planes_leaves_a_pentagon() {
+        let polygon = clip([
+            vertex(0.0, 0.0, 0.0),
+            vertex(1.0, 0.0, 5.0),
+            vertex(0.0, 1.0, 20.0),
+        ]);
+
+        assert_eq!(polygon.len(), MAX_CLIPPED_VERTICES);
+        assert_within_depth(&polygon);
+    }
+
+    #[test]
+    fn clipping_interpolates_every_attribute() {
+        let a = Vertex::new(0.0, 0.0, 0.0, 0.0, 1.0);
+        let b = Vertex::new(4.0, 2.0, 2.0, 1.0, 0.0);
+        let polygon = clip([a, b, Vertex::new(0.0, 2.0, 2.0, 0.0, 0.0)]);
+
+        // The a-b edge crosses z = 1 halfway along.
+        let crossing = polygon.iter().find(|v| v.z == NEAR && v.x > 0.0).unwrap();
+        let expected = a.lerp(&b, 0.5);
+        assert_eq!(
+            (crossing.x, crossing.y, crossing.u, crossing.v),
+            (expected.x, expected.y, expected.u, expected.v)
+        );
+        assert_eq!(
+            (expected.x, expected.y, expected.u, expected.v),
+            (2.0, 1.0, 0.5, 0.5)
+        );
+    }
+
+    #[test]
+    fn line_inside_is_unchanged() {
+        let (a, b) =
+            clip_line_depth(vertex(0.0, 0.0, 2.0), vertex(1.0, 1.0, 9.0), NEAR, FAR).unwrap();
+
+        assert_eq!((a.x, a.z, b.x, b.z), (0.0, 2.0, 1.0, 9.0));
+    }
+
+    #[test]
+    fn line_outside_is_dropped() {
+        assert!(
+            clip_line_depth(vertex(0.0, 0.0, -1.0), vertex(1.0, 0.0, 0.5), NEAR, FAR).is_none()
+        );
+        assert!(
+            clip_line_depth(vertex(0.0, 0.0, 11.0), vertex(1.0, 0.0, 30.0), NEAR, FAR).is_none()
+        );
+    }
+
+    #[test]
+    fn line_is_trimmed_to_both_planes() {
+        let a = Vertex::new(0.0, 0.0, 0.0, 0.0, 0.0);
+        let b = Vertex::new(20.0, 0.0, 20.0, 1.0, 0.0);
+
+        let (near, far) = clip_line_depth(a, b, NEAR, FAR).unwrap();
+        assert_eq!((near.x, near.z, near.u), (1.0, 1.0, 0.05));
+        assert_eq!((far.x, far.z, far.u), (10.0, 10.0, 0.5));
+
+        // The direction of the segment is kept.
+        let (far, near) = clip_line_depth(b, a, NEAR, FAR).unwrap();
+        assert_eq!((near.z, far.z), (1.0, 10.0));
+    }
 }
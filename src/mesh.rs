@@ -1,24 +1,98 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::{
     loader::load_wavefront,
-    primitives::{material::Material, triangle::Triangle, vector::Vec3, vertex::Vertex},
+    primitives::{material::Material, triangle::Triangle, vertex::Vertex},
+    transform::Transform,
 };
 
 #[derive(Debug)]
 pub struct Mesh {
     pub vertices: Vec<Vertex>,
     pub triangles: Vec<Triangle>,
-    pub centre: Vec3,
-    pub rotate_around_pivot: bool,
-    pub pivot: Option<Vec3>,
+    pub transform: Transform,
     pub materials: Vec<Material>,
+    /// Files the mesh was imported from: the OBJ first, then any MTL and
+    /// texture files it pulled in.
+    pub sources: Vec<PathBuf>,
 }
 
 impl Mesh {
-    pub fn custom(obj_path: &Path, centre: Vec3) -> Self {
+    pub fn custom(obj_path: &Path, transform: Transform) -> Self {
         let mut mesh = load_wavefront(obj_path).expect("Error reading OBJ");
-        mesh.centre = centre;
+        mesh.transform = transform;
         mesh
     }
+
+    /// Distance from the mesh origin to its farthest vertex.
+    pub fn bounding_radius(&self) -> f64 {
+        self.vertices
+            .iter()
+            .map(|v| (v.x * v.x + v.y * v.y + v.z * v.z).sqrt())
+            .fold(0.0, f64::max)
+    }
+
+    /// Re-imports geometry and materials from the source OBJ, keeping the
+    /// mesh's transform. On failure the mesh is left untouched.
+    pub fn reload(&mut self) -> Result<(), String> {
+        let path = self.sources.first().ok_or("Mesh has no source file")?;
+        let loaded = load_wavefront(path)?;
+
+        self.vertices = loaded.vertices;
+        self.triangles = loaded.triangles;
+        self.materials = loaded.materials;
+        self.sources = loaded.sources;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::primitives::vector::Vec3;
+
+    #[test]
+    fn reload_swaps_in_new_geometry_and_keeps_it_on_failure() {
+        let dir =
+            std::env::temp_dir().join(format!("software_renderer_mesh_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (obj, mtl) = (dir.join("model.obj"), dir.join("model.mtl"));
+
+        fs::write(&mtl, "newmtl Red\nKd 1 0 0\n").unwrap();
+        fs::write(
+            &obj,
+            "mtllib model.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\n\
+             usemtl Red\nf 1/1/1 2/1/1 3/1/1\n",
+        )
+        .unwrap();
+
+        let transform = Transform {
+            translation: Vec3::new(1.0, 2.0, 3.0),
+            ..Transform::default()
+        };
+        let mut mesh = Mesh::custom(&obj, transform);
+        assert_eq!(mesh.sources, [obj.clone(), mtl.clone()]);
+
+        // New geometry and a dropped MTL both come through; the transform
+        // stays.
+        fs::write(
+            &obj,
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\n\
+             f 1/1/1 2/1/1 3/1/1\nf 1/1/1 3/1/1 4/1/1\n",
+        )
+        .unwrap();
+        mesh.reload().unwrap();
+        assert_eq!((mesh.vertices.len(), mesh.triangles.len()), (4, 2));
+        assert_eq!(mesh.sources, std::slice::from_ref(&obj));
+        assert_eq!(mesh.transform.translation, transform.translation);
+
+        fs::write(&obj, "vt 0 0\nvn 0 0 1\nf 1/1/1 2/1/1 3/1/1\n").unwrap();
+        assert!(mesh.reload().is_err());
+        assert_eq!((mesh.vertices.len(), mesh.triangles.len()), (4, 2));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,85 @@
+/// Samples per pixel for multisample anti-aliasing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Msaa {
+    /// One sample per pixel, taken by the regular rasterizer.
+    #[default]
+    Off,
+    X2,
+    X4,
+    X8,
+}
+
+impl Msaa {
+    pub fn samples(self) -> usize {
+        match self {
+            Self::Off => 1,
+            Self::X2 => 2,
+            Self::X4 => 4,
+            Self::X8 => 8,
+        }
+    }
+
+    /// Sample offsets from the pixel centre in pixels, in the standard
+    /// Direct3D patterns.
+    pub fn positions(self) -> &'static [(f64, f64)] {
+        const X1: [(f64, f64); 1] = [(0.0, 0.0)];
+        const X2: [(f64, f64); 2] = [(4.0 / 16.0, 4.0 / 16.0), (-4.0 / 16.0, -4.0 / 16.0)];
+        const X4: [(f64, f64); 4] = [
+            (-2.0 / 16.0, -6.0 / 16.0),
+            (6.0 / 16.0, -2.0 / 16.0),
+            (-6.0 / 16.0, 2.0 / 16.0),
+            (2.0 / 16.0, 6.0 / 16.0),
+        ];
+        const X8: [(f64, f64); 8] = [
+            (1.0 / 16.0, -3.0 / 16.0),
+            (-1.0 / 16.0, 3.0 / 16.0),
+            (5.0 / 16.0, 1.0 / 16.0),
+            (-3.0 / 16.0, -5.0 / 16.0),
+            (-5.0 / 16.0, 5.0 / 16.0),
+            (-7.0 / 16.0, -1.0 / 16.0),
+            (3.0 / 16.0, 7.0 / 16.0),
+            (7.0 / 16.0, -7.0 / 16.0),
+        ];
+
+        match self {
+            Self::Off => &X1,
+            Self::X2 => &X2,
+            Self::X4 => &X4,
+            Self::X8 => &X8,
+        }
+    }
+
+    /// The setting after this one, wrapping around, for cycling with a key.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::X2,
+            Self::X2 => Self::X4,
+            Self::X4 => Self::X8,
+            Self::X8 => Self::Off,
+        }
+    }
+}
+
+/// Colour, depth and stencil for every sample of every pixel, stored
+/// pixel by pixel with a pixel's samples next to each other.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SampleBuffers {
+    pub msaa: Msaa,
+    /// Linear-light RGBA, averaged into the pixel on resolve.
+    pub colour: Vec<[f32; 4]>,
+    /// Encoded like [`crate::renderer::Renderer::depth`].
+    pub depth: Vec<f64>,
+    pub stencil: Vec<u8>,
+}
+
+impl SampleBuffers {
+    pub fn new(msaa: Msaa, pixels: usize, depth_clear: f64) -> Self {
+        let len = pixels * msaa.samples();
+        Self {
+            msaa,
+            colour: vec![[0.0, 0.0, 0.0, 1.0]; len],
+            depth: vec![depth_clear; len],
+            stencil: vec![0; len],
+        }
+    }
+}
@@ -0,0 +1,780 @@
+use std::{fmt::Debug, mem::swap};
+
+use crate::{primitives::colour::Colour, renderer::Renderer, target::RenderTarget};
+
+/// A full-screen pass over a finished frame.
+pub trait PostProcess: Debug {
+    /// Writes every pixel of `output.buffer` from `input`. Both targets
+    /// have the same size, and `input.depth` holds view-space depth, with
+    /// infinity where nothing was drawn.
+    fn apply(&mut self, input: &RenderTarget, output: &mut RenderTarget);
+}
+
+/// An ordered list of passes, each reading the previous one's output.
+#[derive(Debug, Default)]
+pub struct PostChain {
+    pub passes: Vec<Box<dyn PostProcess>>,
+    source: RenderTarget,
+    scratch: RenderTarget,
+}
+
+impl PostChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, pass: impl PostProcess + 'static) -> &mut Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    /// Runs every pass over the renderer's colour buffer and writes the
+    /// result back into it. Depth and stencil are left unchanged.
+    pub fn apply(&mut self, renderer: &mut Renderer) {
+        if self.passes.is_empty() {
+            return;
+        }
+
+        if (self.source.width, self.source.height) != (renderer.width, renderer.height) {
+            self.source.resize(renderer.width, renderer.height);
+            self.scratch.resize(renderer.width, renderer.height);
+        }
+
+        let mode = renderer.depth_state.mode;
+        let near = renderer.depth_near();
+        self.source.buffer.copy_from_slice(&renderer.buffer);
+        for (out, &d) in self.source.depth.iter_mut().zip(&renderer.depth) {
+            *out = mode.decode(d, near);
+        }
+
+        for pass in self.passes.iter_mut() {
+            pass.apply(&self.source, &mut self.scratch);
+            swap(&mut self.source.buffer, &mut self.scratch.buffer);
+        }
+
+        renderer.buffer.copy_from_slice(&self.source.buffer);
+    }
+}
+
+/// Raises colours to `1 / gamma`, as a brightness curve: above 1
+/// brightens mid-tones, below 1 darkens them.
+///
+/// The renderer's colour buffer already holds sRGB, so this is not needed
+/// to encode the output, and the default of 1 changes nothing. A gamma of
+/// 2.2 would encode the frame a second time and wash it out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gamma {
+    pub gamma: f64,
+}
+
+impl Default for Gamma {
+    fn default() -> Self {
+        Self { gamma: 1.0 }
+    }
+}
+
+impl PostProcess for Gamma {
+    fn apply(&mut self, input: &RenderTarget, output: &mut RenderTarget) {
+        let mut table = [0u8; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            *entry = to_byte((i as f64 / 255.0).powf(1.0 / self.gamma));
+        }
+
+        for (out, px) in output
+            .buffer
+            .chunks_exact_mut(4)
+            .zip(input.buffer.chunks_exact(4))
+        {
+            out[0] = table[px[0] as usize];
+            out[1] = table[px[1] as usize];
+            out[2] = table[px[2] as usize];
+            out[3] = px[3];
+        }
+    }
+}
+
+/// Darkens the frame towards its corners.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vignette {
+    /// How dark the corners get, from 0 (unchanged) to 1 (black).
+    pub strength: f64,
+    /// Distance from the centre where darkening starts, with the corners
+    /// at 1.
+    pub radius: f64,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            strength: 0.6,
+            radius: 0.5,
+        }
+    }
+}
+
+impl PostProcess for Vignette {
+    fn apply(&mut self, input: &RenderTarget, output: &mut RenderTarget) {
+        let cx = input.width as f64 / 2.0;
+        let cy = input.height as f64 / 2.0;
+        let corner = (cx * cx + cy * cy).sqrt();
+
+        for y in 0..input.height {
+            for x in 0..input.width {
+                let dx = x as f64 + 0.5 - cx;
+                let dy = y as f64 + 0.5 - cy;
+                let d = (dx * dx + dy * dy).sqrt() / corner;
+                let factor = 1.0 - self.strength * smoothstep(self.radius, 1.0, d);
+
+                let i = (y * input.width + x) * 4;
+                for c in 0..3 {
+                    output.buffer[i + c] = (input.buffer[i + c] as f64 * factor).round() as u8;
+                }
+                output.buffer[i + 3] = input.buffer[i + 3];
+            }
+        }
+    }
+}
+
+/// Draws lines where Sobel filters find edges in depth or brightness.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outline {
+    pub colour: Colour,
+    /// Depth edge strength, relative to the nearest depth around a pixel,
+    /// above which it is outlined.
+    pub depth_threshold: f64,
+    /// Brightness edge strength, in 0-1 luminance, above which a pixel is
+    /// outlined.
+    pub luminance_threshold: f64,
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Self {
+            colour: Colour::new(0, 0, 0, 255),
+            depth_threshold: 0.25,
+            luminance_threshold: 0.5,
+        }
+    }
+}
+
+impl PostProcess for Outline {
+    fn apply(&mut self, input: &RenderTarget, output: &mut RenderTarget) {
+        // Inverse depth is finite for empty pixels (0), so silhouettes
+        // against the background are edges like any other.
+        let inverse_depth = |x: usize, y: usize| 1.0 / input.depth[y * input.width + x];
+        let luminance = |x: usize, y: usize| luminance(&input.buffer[(y * input.width + x) * 4..]);
+
+        for y in 0..input.height {
+            for x in 0..input.width {
+                let nearest = neighbourhood(input, x, y)
+                    .map(|(nx, ny)| inverse_depth(nx, ny))
+                    .fold(0.0, f64::max);
+
+                let depth_edge = nearest > 0.0
+                    && sobel(input, x, y, inverse_depth) / nearest > self.depth_threshold;
+                let luminance_edge = sobel(input, x, y, luminance) > self.luminance_threshold;
+
+                let i = (y * input.width + x) * 4;
+                if depth_edge || luminance_edge {
+                    output.buffer[i] = self.colour.red;
+                    output.buffer[i + 1] = self.colour.green;
+                    output.buffer[i + 2] = self.colour.blue;
+                    output.buffer[i + 3] = self.colour.alpha;
+                } else {
+                    output.buffer[i..i + 4].copy_from_slice(&input.buffer[i..i + 4]);
+                }
+            }
+        }
+    }
+}
+
+/// Boosts each pixel's difference from its four neighbours.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sharpen {
+    pub amount: f64,
+}
+
+impl Default for Sharpen {
+    fn default() -> Self {
+        Self { amount: 0.5 }
+    }
+}
+
+impl PostProcess for Sharpen {
+    fn apply(&mut self, input: &RenderTarget, output: &mut RenderTarget) {
+        let (w, h) = (input.width, input.height);
+
+        for y in 0..h {
+            for x in 0..w {
+                let i = (y * w + x) * 4;
+                let neighbours = [
+                    (y * w + x.saturating_sub(1)) * 4,
+                    (y * w + (x + 1).min(w - 1)) * 4,
+                    (y.saturating_sub(1) * w + x) * 4,
+                    ((y + 1).min(h - 1) * w + x) * 4,
+                ];
+
+                for c in 0..3 {
+                    let centre = input.buffer[i + c] as f64;
+                    let sum: f64 = neighbours.iter().map(|&n| input.buffer[n + c] as f64).sum();
+                    let value = centre + self.amount * (4.0 * centre - sum);
+                    output.buffer[i + c] = value.round().clamp(0.0, 255.0) as u8;
+                }
+                output.buffer[i + 3] = input.buffer[i + 3];
+            }
+        }
+    }
+}
+
+/// Brightness, contrast, saturation and tint adjustments, in that order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColourGrade {
+    /// Added to every channel, in 0-1 units.
+    pub brightness: f64,
+    /// Scales distance from mid-grey; 1 leaves the image unchanged.
+    pub contrast: f64,
+    /// 0 is greyscale, 1 unchanged, above 1 more vivid.
+    pub saturation: f64,
+    /// Per-channel multipliers for red, green and blue.
+    pub tint: (f64, f64, f64),
+}
+
+impl Default for ColourGrade {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            tint: (1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl PostProcess for ColourGrade {
+    fn apply(&mut self, input: &RenderTarget, output: &mut RenderTarget) {
+        let tint = [self.tint.0, self.tint.1, self.tint.2];
+
+        for (out, px) in output
+            .buffer
+            .chunks_exact_mut(4)
+            .zip(input.buffer.chunks_exact(4))
+        {
+            let mut rgb = [0.0; 3];
+            for c in 0..3 {
+                let v = px[c] as f64 / 255.0 + self.brightness;
+                rgb[c] = (v - 0.5) * self.contrast + 0.5;
+            }
+
+            let grey = 0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2];
+            for c in 0..3 {
+                out[c] = to_byte((grey + (rgb[c] - grey) * self.saturation) * tint[c]);
+            }
+            out[3] = px[3];
+        }
+    }
+}
+
+/// Screen-space anti-aliasing for the finished frame, a cheaper
+/// alternative to MSAA.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AntiAliasing {
+    #[default]
+    Off,
+    /// The [`Fxaa`] pass.
+    Fxaa,
+    /// Jittered frames accumulated by [`crate::taa::Taa`].
+    Taa,
+}
+
+impl AntiAliasing {
+    /// The mode after this one, wrapping around, for cycling with a key.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Fxaa,
+            Self::Fxaa => Self::Taa,
+            Self::Taa => Self::Off,
+        }
+    }
+}
+
+/// Fast approximate anti-aliasing: finds edges by their luminance contrast
+/// and blends each edge pixel with its neighbour across the edge, more the
+/// closer it is to the end of a stair step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fxaa {
+    /// Luminance contrast below which a pixel is never treated as an edge.
+    pub contrast_threshold: f64,
+    /// Contrast, relative to the brightest neighbour, needed for an edge.
+    pub relative_threshold: f64,
+    /// How much single-pixel details are smoothed, from 0 to 1.
+    pub subpixel: f64,
+}
+
+impl Default for Fxaa {
+    fn default() -> Self {
+        Self {
+            contrast_threshold: 0.0312,
+            relative_threshold: 0.125,
+            subpixel: 0.75,
+        }
+    }
+}
+
+/// Pixels walked along an edge in each direction to find where it ends.
+const FXAA_SEARCH_STEPS: usize = 10;
+
+impl PostProcess for Fxaa {
+    fn apply(&mut self, input: &RenderTarget, output: &mut RenderTarget) {
+        let (w, h) = (input.width, input.height);
+        let luma: Vec<f64> = input.buffer.chunks_exact(4).map(luminance).collect();
+        let l = |x: i64, y: i64| {
+            let x = x.clamp(0, w as i64 - 1) as usize;
+            let y = y.clamp(0, h as i64 - 1) as usize;
+            luma[y * w + x]
+        };
+        let luma_at = |x: f64, y: f64| bilinear(w, h, x, y, |x, y| [luma[y * w + x]])[0];
+
+        for y in 0..h {
+            for x in 0..w {
+                let i = (y * w + x) * 4;
+                let (xi, yi) = (x as i64, y as i64);
+
+                let m = l(xi, yi);
+                let (n, s) = (l(xi, yi - 1), l(xi, yi + 1));
+                let (e, west) = (l(xi + 1, yi), l(xi - 1, yi));
+
+                let highest = m.max(n).max(s).max(e).max(west);
+                let lowest = m.min(n).min(s).min(e).min(west);
+                let contrast = highest - lowest;
+
+                if contrast
+                    < self
+                        .contrast_threshold
+                        .max(self.relative_threshold * highest)
+                {
+                    output.buffer[i..i + 4].copy_from_slice(&input.buffer[i..i + 4]);
+                    continue;
+                }
+
+                let (ne, nw) = (l(xi + 1, yi - 1), l(xi - 1, yi - 1));
+                let (se, sw) = (l(xi + 1, yi + 1), l(xi - 1, yi + 1));
+
+                // Subpixel blend from how far the pixel stands out from the
+                // average of its neighbours.
+                let average = (2.0 * (n + s + e + west) + ne + nw + se + sw) / 12.0;
+                let subpixel = smoothstep(0.0, 1.0, ((average - m).abs() / contrast).min(1.0));
+                let subpixel_blend = subpixel * subpixel * self.subpixel;
+
+                let horizontal = 2.0 * (n + s - 2.0 * m).abs()
+                    + (ne + se - 2.0 * e).abs()
+                    + (nw + sw - 2.0 * west).abs();
+                let vertical = 2.0 * (e + west - 2.0 * m).abs()
+                    + (ne + nw - 2.0 * n).abs()
+                    + (se + sw - 2.0 * s).abs();
+                let is_horizontal = horizontal >= vertical;
+
+                // Which neighbour across the edge differs most from this one.
+                let (positive, negative) = if is_horizontal { (s, n) } else { (e, west) };
+                let (step, opposite) = if (positive - m).abs() >= (negative - m).abs() {
+                    (1.0, positive)
+                } else {
+                    (-1.0, negative)
+                };
+                let gradient = (opposite - m).abs();
+
+                // Walk both ways along the edge, half a pixel towards the
+                // opposite side, until the luminance no longer matches it.
+                let (across, along) = if is_horizontal {
+                    ((0.0, step), (1.0, 0.0))
+                } else {
+                    ((step, 0.0), (0.0, 1.0))
+                };
+                let edge = (x as f64 + across.0 * 0.5, y as f64 + across.1 * 0.5);
+                let edge_luma = (m + opposite) / 2.0;
+
+                let walk = |direction: f64| {
+                    let mut delta = 0.0;
+                    for i in 1..=FXAA_SEARCH_STEPS {
+                        let t = i as f64 * direction;
+                        delta = luma_at(edge.0 + along.0 * t, edge.1 + along.1 * t) - edge_luma;
+                        if delta.abs() >= gradient / 4.0 {
+                            return (i as f64, delta);
+                        }
+                    }
+                    (FXAA_SEARCH_STEPS as f64, delta)
+                };
+                let (positive_distance, positive_delta) = walk(1.0);
+                let (negative_distance, negative_delta) = walk(-1.0);
+
+                let (distance, delta) = if positive_distance <= negative_distance {
+                    (positive_distance, positive_delta)
+                } else {
+                    (negative_distance, negative_delta)
+                };
+
+                // Only the end where the edge steps away from this pixel's
+                // side is blended.
+                let edge_blend = if (m - edge_luma < 0.0) == (delta < 0.0) {
+                    0.0
+                } else {
+                    0.5 - distance / (positive_distance + negative_distance)
+                };
+
+                let blend = edge_blend.max(subpixel_blend);
+                let colour: [f64; 4] = bilinear(
+                    w,
+                    h,
+                    x as f64 + across.0 * blend,
+                    y as f64 + across.1 * blend,
+                    |x, y| {
+                        let j = (y * w + x) * 4;
+                        std::array::from_fn(|c| input.buffer[j + c] as f64)
+                    },
+                );
+
+                for (out, value) in output.buffer[i..i + 4].iter_mut().zip(colour) {
+                    *out = value.round() as u8;
+                }
+            }
+        }
+    }
+}
+
+/// Converts a 0-1 value to a byte, clamping out-of-range values.
+fn to_byte(v: f64) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn luminance(px: &[u8]) -> f64 {
+    (0.2126 * px[0] as f64 + 0.7152 * px[1] as f64 + 0.0722 * px[2] as f64) / 255.0
+}
+
+fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Interpolates `read` between the four pixels around (`x`, `y`), with
+/// pixel centres at whole coordinates and edges clamped.
+fn bilinear<const N: usize>(
+    width: usize,
+    height: usize,
+    x: f64,
+    y: f64,
+    read: impl Fn(usize, usize) -> [f64; N],
+) -> [f64; N] {
+    let x = x.clamp(0.0, (width - 1) as f64);
+    let y = y.clamp(0.0, (height - 1) as f64);
+    let (x0, y0) = (x as usize, y as usize);
+    let x1 = (x0 + 1).min(width - 1);
+    let y1 = (y0 + 1).min(height - 1);
+    let (tx, ty) = (x - x0 as f64, y - y0 as f64);
+
+    let (a, b) = (read(x0, y0), read(x1, y0));
+    let (c, d) = (read(x0, y1), read(x1, y1));
+
+    std::array::from_fn(|i| {
+        let top = a[i] + (b[i] - a[i]) * tx;
+        let bottom = c[i] + (d[i] - c[i]) * tx;
+        top + (bottom - top) * ty
+    })
+}
+
+/// The 3x3 block around a pixel, clamped to the image.
+fn neighbourhood(
+    target: &RenderTarget,
+    x: usize,
+    y: usize,
+) -> impl Iterator<Item = (usize, usize)> + use<> {
+    let (w, h) = (target.width, target.height);
+
+    (0..9).map(move |i| {
+        let nx = (x + i % 3).saturating_sub(1).min(w - 1);
+        let ny = (y + i / 3).saturating_sub(1).min(h - 1);
+        (nx, ny)
+    })
+}
+
+/// Gradient magnitude of `sample` at a pixel, using Sobel kernels.
+fn sobel(target: &RenderTarget, x: usize, y: usize, sample: impl Fn(usize, usize) -> f64) -> f64 {
+    const GX: [f64; 9] = [-1.0, 0.0, 1.0, -2.0, 0.0, 2.0, -1.0, 0.0, 1.0];
+    const GY: [f64; 9] = [-1.0, -2.0, -1.0, 0.0, 0.0, 0.0, 1.0, 2.0, 1.0];
+
+    let (mut gx, mut gy) = (0.0, 0.0);
+    for (i, (nx, ny)) in neighbourhood(target, x, y).enumerate() {
+        let s = sample(nx, ny);
+        gx += GX[i] * s;
+        gy += GY[i] * s;
+    }
+
+    (gx * gx + gy * gy).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{DepthMode, DepthState};
+
+    /// A 256x1 image holding every byte value in each channel, with
+    /// different orders so the channels are not all equal.
+    fn gradient() -> RenderTarget {
+        let mut target = RenderTarget::new(256, 1);
+        for (i, px) in target.buffer.chunks_exact_mut(4).enumerate() {
+            px.copy_from_slice(&[i as u8, 255 - i as u8, (i * 7) as u8, (i * 3) as u8]);
+        }
+        target
+    }
+
+    fn run(pass: &mut impl PostProcess, input: &RenderTarget) -> RenderTarget {
+        let mut output = RenderTarget::new(input.width, input.height);
+        pass.apply(input, &mut output);
+        output
+    }
+
+    /// Every pixel `colour`, with depth `depth`.
+    fn flat(width: usize, height: usize, colour: [u8; 4], depth: f64) -> RenderTarget {
+        let mut target = RenderTarget::new(width, height);
+        for px in target.buffer.chunks_exact_mut(4) {
+            px.copy_from_slice(&colour);
+        }
+        target.depth.fill(depth);
+        target
+    }
+
+    fn rgba(target: &RenderTarget, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * target.width + x) * 4;
+        target.buffer[i..i + 4].try_into().unwrap()
+    }
+
+    /// Black on the left half, white on the right.
+    fn vertical_edge(width: usize, height: usize) -> RenderTarget {
+        let mut target = RenderTarget::new(width, height);
+        for (i, px) in target.buffer.chunks_exact_mut(4).enumerate() {
+            let value = if i % width < width / 2 { 0 } else { 255 };
+            px.copy_from_slice(&[value, value, value, 255]);
+        }
+        target
+    }
+
+    /// Adds its value to the red channel.
+    #[derive(Debug)]
+    struct AddRed(u8);
+
+    impl PostProcess for AddRed {
+        fn apply(&mut self, input: &RenderTarget, output: &mut RenderTarget) {
+            output.buffer.copy_from_slice(&input.buffer);
+            for px in output.buffer.chunks_exact_mut(4) {
+                px[0] = px[0].wrapping_add(self.0);
+            }
+        }
+    }
+
+    /// Doubles the red channel.
+    #[derive(Debug)]
+    struct DoubleRed;
+
+    impl PostProcess for DoubleRed {
+        fn apply(&mut self, input: &RenderTarget, output: &mut RenderTarget) {
+            output.buffer.copy_from_slice(&input.buffer);
+            for px in output.buffer.chunks_exact_mut(4) {
+                px[0] = px[0].wrapping_mul(2);
+            }
+        }
+    }
+
+    #[test]
+    fn identity_grade_and_unit_gamma_change_nothing() {
+        let input = gradient();
+
+        assert_eq!(
+            run(&mut ColourGrade::default(), &input).buffer,
+            input.buffer
+        );
+        assert_eq!(run(&mut Gamma::default(), &input).buffer, input.buffer);
+    }
+
+    #[test]
+    fn chain_runs_passes_in_order() {
+        let mut renderer = Renderer::new(2, 2);
+        renderer.clear(Colour::new(10, 0, 0, 255));
+
+        let mut chain = PostChain::new();
+        chain.push(AddRed(1)).push(DoubleRed);
+        chain.apply(&mut renderer);
+
+        // (10 + 1) * 2, not 10 * 2 + 1.
+        assert!(renderer.buffer.chunks_exact(4).all(|px| px[0] == 22));
+    }
+
+    #[test]
+    fn chain_output_lands_in_the_renderer_for_any_pass_count() {
+        for passes in 1..=4 {
+            let mut renderer = Renderer::new(3, 2);
+            renderer.clear(Colour::new(0, 0, 0, 255));
+
+            let mut chain = PostChain::new();
+            for _ in 0..passes {
+                chain.push(AddRed(5));
+            }
+            chain.apply(&mut renderer);
+
+            let red = 5 * passes as u8;
+            assert!(
+                renderer
+                    .buffer
+                    .chunks_exact(4)
+                    .all(|px| px == [red, 0, 0, 255]),
+                "{passes} passes"
+            );
+        }
+    }
+
+    #[test]
+    fn passes_see_view_space_depth() {
+        /// Fails the test unless its input depth is the expected one.
+        #[derive(Debug)]
+        struct ExpectDepth(Vec<f64>);
+
+        impl PostProcess for ExpectDepth {
+            fn apply(&mut self, input: &RenderTarget, output: &mut RenderTarget) {
+                assert_eq!(input.depth, self.0);
+                output.buffer.copy_from_slice(&input.buffer);
+            }
+        }
+
+        let mut renderer = Renderer::new(2, 1);
+        renderer.depth_state = DepthState::with_mode(DepthMode::ReversedZ);
+        renderer.clear(Colour::new(0, 0, 0, 255));
+        renderer.put_pixel_depth(0, 0, 4.0, Colour::new(255, 255, 255, 255));
+
+        let mut chain = PostChain::new();
+        chain.push(ExpectDepth(vec![4.0, f64::INFINITY]));
+        chain.apply(&mut renderer);
+    }
+
+    #[test]
+    fn fxaa_softens_a_hard_edge() {
+        let (w, h) = (16, 8);
+        let input = vertical_edge(w, h);
+        let output = run(&mut Fxaa::default(), &input);
+
+        for y in 0..h {
+            let red = |x: usize| output.buffer[(y * w + x) * 4];
+            // Both sides of the edge move towards each other.
+            assert!(red(w / 2 - 1) > 0, "row {y}");
+            assert!(red(w / 2) < 255, "row {y}");
+            // Pixels away from it are untouched.
+            assert_eq!(red(2), 0);
+            assert_eq!(red(w - 3), 255);
+        }
+        assert!(output.buffer.chunks_exact(4).all(|px| px[3] == 255));
+    }
+
+    #[test]
+    fn fxaa_leaves_a_flat_image_alone() {
+        let mut input = RenderTarget::new(9, 7);
+        for px in input.buffer.chunks_exact_mut(4) {
+            px.copy_from_slice(&[90, 140, 200, 255]);
+        }
+
+        assert_eq!(run(&mut Fxaa::default(), &input).buffer, input.buffer);
+    }
+
+    #[test]
+    fn gamma_above_one_brightens_mid_tones_only() {
+        let output = run(&mut Gamma { gamma: 2.0 }, &gradient());
+
+        for (out, px) in output
+            .buffer
+            .chunks_exact(4)
+            .zip(gradient().buffer.chunks_exact(4))
+        {
+            assert!(out[0] >= px[0]);
+            assert_eq!(out[3], px[3]);
+        }
+        assert_eq!(rgba(&output, 0, 0)[0], 0);
+        assert_eq!(rgba(&output, 255, 0)[0], 255);
+        // sqrt(64 / 255) * 255
+        assert_eq!(rgba(&output, 64, 0)[0], 128);
+    }
+
+    #[test]
+    fn vignette_darkens_only_towards_the_corners() {
+        let input = flat(20, 20, [200, 100, 50, 255], 1.0);
+        let output = run(&mut Vignette::default(), &input);
+
+        assert_eq!(rgba(&output, 10, 10), [200, 100, 50, 255]);
+        let corner = rgba(&output, 0, 0);
+        assert!(corner[0] < 120, "{corner:?}");
+        assert_eq!(corner[3], 255);
+
+        // Darkening grows outwards along a row.
+        let row: Vec<u8> = (0..10).map(|x| rgba(&output, x, 10)[0]).collect();
+        assert!(row.windows(2).all(|w| w[0] <= w[1]), "{row:?}");
+
+        let none = Vignette {
+            strength: 0.0,
+            ..Vignette::default()
+        };
+        assert_eq!(run(&mut { none }, &input).buffer, input.buffer);
+    }
+
+    #[test]
+    fn outline_marks_depth_steps_and_bright_edges() {
+        // Same colour throughout, nearer on the left.
+        let mut input = flat(12, 6, [128, 128, 128, 255], 10.0);
+        for y in 0..6 {
+            for x in 0..6 {
+                input.depth[y * 12 + x] = 2.0;
+            }
+        }
+        let output = run(&mut Outline::default(), &input);
+        for y in 0..6 {
+            let outlined: Vec<usize> = (0..12)
+                .filter(|&x| rgba(&output, x, y) == [0, 0, 0, 255])
+                .collect();
+            assert!(
+                !outlined.is_empty() && outlined.iter().all(|x| (5..=6).contains(x)),
+                "{outlined:?}"
+            );
+        }
+
+        // Flat depth with a black to white step.
+        let mut input = vertical_edge(12, 6);
+        input.depth.fill(10.0);
+        let red = Outline {
+            colour: Colour::new(255, 0, 0, 255),
+            ..Outline::default()
+        };
+        let output = run(&mut { red }, &input);
+        assert_eq!(rgba(&output, 6, 3), [255, 0, 0, 255]);
+        assert_eq!(rgba(&output, 1, 3), [0, 0, 0, 255]);
+        assert_eq!(rgba(&output, 10, 3), [255, 255, 255, 255]);
+
+        // Nothing to outline.
+        let input = flat(8, 8, [40, 90, 200, 255], 5.0);
+        assert_eq!(run(&mut Outline::default(), &input).buffer, input.buffer);
+    }
+
+    #[test]
+    fn sharpen_boosts_a_pixel_against_its_neighbours() {
+        let mut input = flat(5, 5, [100, 100, 100, 255], 1.0);
+        input.buffer[(2 * 5 + 2) * 4] = 120;
+        let output = run(&mut Sharpen::default(), &input);
+
+        // 120 + 0.5 * (4 * 120 - 400), and each neighbour loses half the
+        // difference.
+        assert_eq!(rgba(&output, 2, 2), [160, 100, 100, 255]);
+        assert_eq!(rgba(&output, 1, 2), [90, 100, 100, 255]);
+        assert_eq!(rgba(&output, 0, 0), [100, 100, 100, 255]);
+
+        let flat_input = flat(5, 5, [100, 100, 100, 255], 1.0);
+        assert_eq!(
+            run(&mut Sharpen::default(), &flat_input).buffer,
+            flat_input.buffer
+        );
+
+        let off = Sharpen { amount: 0.0 };
+        assert_eq!(run(&mut { off }, &input).buffer, input.buffer);
+    }
+}
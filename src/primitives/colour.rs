@@ -1,3 +1,5 @@
+use std::sync::LazyLock;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Colour {
     pub red: u8,
@@ -7,7 +9,7 @@ pub struct Colour {
 }
 
 impl Colour {
-    pub fn new(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+    pub const fn new(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
         Self {
             red,
             green,
@@ -15,4 +17,87 @@ impl Colour {
             alpha,
         }
     }
+
+    /// Linear-light RGBA in 0-1, treating this colour as sRGB. Alpha is
+    /// already linear and is only rescaled.
+    pub fn to_linear(self) -> [f32; 4] {
+        [
+            srgb_to_linear(self.red),
+            srgb_to_linear(self.green),
+            srgb_to_linear(self.blue),
+            self.alpha as f32 / 255.0,
+        ]
+    }
+
+    /// Encodes linear-light RGBA as sRGB, clamping to 0-1 first.
+    pub fn from_linear(rgba: [f32; 4]) -> Self {
+        Self::new(
+            linear_to_srgb(rgba[0]),
+            linear_to_srgb(rgba[1]),
+            linear_to_srgb(rgba[2]),
+            (rgba[3].clamp(0.0, 1.0) * 255.0).round() as u8,
+        )
+    }
+}
+
+static SRGB_TO_LINEAR: LazyLock<[f32; 256]> = LazyLock::new(|| {
+    let mut table = [0.0; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let c = i as f32 / 255.0;
+        *entry = if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        };
+    }
+    table
+});
+
+/// Decodes an sRGB channel to linear light in 0-1.
+pub fn srgb_to_linear(c: u8) -> f32 {
+    SRGB_TO_LINEAR[c as usize]
+}
+
+/// Encodes a linear-light channel as sRGB, clamping to 0-1 first.
+pub fn linear_to_srgb(v: f32) -> u8 {
+    let v = v.clamp(0.0, 1.0);
+    let c = if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+
+    (c * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_byte_survives_a_round_trip_through_linear() {
+        for c in 0..=255u8 {
+            assert_eq!(linear_to_srgb(srgb_to_linear(c)), c);
+
+            let colour = Colour::new(c, 255 - c, c / 2, c);
+            assert_eq!(Colour::from_linear(colour.to_linear()), colour);
+        }
+    }
+
+    #[test]
+    fn linear_values_are_clamped() {
+        assert_eq!(
+            Colour::from_linear([-1.0, 2.0, 0.5, 3.0]),
+            Colour::new(0, 255, 188, 255)
+        );
+    }
+
+    #[test]
+    fn decoding_follows_the_srgb_curve() {
+        assert_eq!(srgb_to_linear(0), 0.0);
+        assert_eq!(srgb_to_linear(255), 1.0);
+        assert!((srgb_to_linear(188) - 0.5029).abs() < 1e-4);
+        // The linear segment near black.
+        assert!((srgb_to_linear(10) - 10.0 / 255.0 / 12.92).abs() < 1e-7);
+    }
 }
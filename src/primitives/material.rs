@@ -1,10 +1,22 @@
-use crate::primitives::{colour::Colour, texture::Texture};
+use crate::primitives::{colour::Colour, texture::Texture, vector::Vec3};
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Material {
     pub name: String,
     pub diffuse: Colour,
+    /// Diffuse colour map, decoded as sRGB.
     pub kd_texture: Option<Texture>,
+    /// Tangent-space normal map (`norm`), stored linearly.
+    pub normal_texture: Option<Texture>,
+    /// Height map (`bump` or `map_Bump`), stored linearly.
+    pub bump_texture: Option<Texture>,
+    /// Roughness map, stored linearly.
+    pub roughness_texture: Option<Texture>,
+    /// Light given off in linear units, added on top of the diffuse colour.
+    /// Values above 1 only survive with an HDR buffer.
+    pub emissive: Vec3,
+    /// Draw both sides regardless of the renderer's cull mode.
+    pub double_sided: bool,
 }
 
 impl Default for Material {
@@ -13,6 +25,36 @@ impl Default for Material {
             name: String::from(""),
             diffuse: Colour::new(255, 255, 255, 255),
             kd_texture: None,
+            normal_texture: None,
+            bump_texture: None,
+            roughness_texture: None,
+            emissive: Vec3::default(),
+            double_sided: false,
         }
     }
 }
+
+impl Material {
+    /// Every texture the material uses.
+    pub fn textures(&self) -> impl Iterator<Item = &Texture> {
+        [
+            &self.kd_texture,
+            &self.normal_texture,
+            &self.bump_texture,
+            &self.roughness_texture,
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    pub fn textures_mut(&mut self) -> impl Iterator<Item = &mut Texture> {
+        [
+            &mut self.kd_texture,
+            &mut self.normal_texture,
+            &mut self.bump_texture,
+            &mut self.roughness_texture,
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
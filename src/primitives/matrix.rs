@@ -0,0 +1,498 @@
+use std::ops::Mul;
+
+use crate::primitives::{
+    quaternion::Quat,
+    vector::{Vec3, Vec4},
+};
+
+/// Row-major 3x3 matrix acting on column vectors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat3 {
+    pub rows: [[f64; 3]; 3],
+}
+
+/// Row-major 4x4 matrix acting on column vectors.
+///
+/// Builders follow the renderer's left-handed convention: +X right, +Y up
+/// and +Z into the screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    pub rows: [[f64; 4]; 4],
+}
+
+impl Default for Mat3 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mat3 {
+    pub const IDENTITY: Self = Self {
+        rows: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+    };
+
+    pub fn from_rows(rows: [[f64; 3]; 3]) -> Self {
+        Self { rows }
+    }
+
+    pub fn from_quat(q: Quat) -> Self {
+        let Quat { x, y, z, w } = q;
+        let (xx, yy, zz) = (x * x, y * y, z * z);
+        let (xy, xz, yz) = (x * y, x * z, y * z);
+        let (wx, wy, wz) = (w * x, w * y, w * z);
+
+        Self::from_rows([
+            [1.0 - 2.0 * (yy + zz), 2.0 * (xy - wz), 2.0 * (xz + wy)],
+            [2.0 * (xy + wz), 1.0 - 2.0 * (xx + zz), 2.0 * (yz - wx)],
+            [2.0 * (xz - wy), 2.0 * (yz + wx), 1.0 - 2.0 * (xx + yy)],
+        ])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let m = &self.rows;
+        let mut rows = [[0.0; 3]; 3];
+
+        for (r, row) in rows.iter_mut().enumerate() {
+            for (c, cell) in row.iter_mut().enumerate() {
+                *cell = m[c][r];
+            }
+        }
+
+        Self { rows }
+    }
+
+    pub fn determinant(&self) -> f64 {
+        let m = &self.rows;
+
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// Returns `None` if the matrix is singular. Only a determinant that is
+    /// zero, subnormal or not finite counts, so small but well-conditioned
+    /// matrices such as tiny uniform scales still invert.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if !det.is_normal() {
+            return None;
+        }
+
+        let m = &self.rows;
+        let inv_det = 1.0 / det;
+
+        Some(Self::from_rows([
+            [
+                (m[1][1] * m[2][2] - m[1][2] * m[2][1]) * inv_det,
+                (m[0][2] * m[2][1] - m[0][1] * m[2][2]) * inv_det,
+                (m[0][1] * m[1][2] - m[0][2] * m[1][1]) * inv_det,
+            ],
+            [
+                (m[1][2] * m[2][0] - m[1][0] * m[2][2]) * inv_det,
+                (m[0][0] * m[2][2] - m[0][2] * m[2][0]) * inv_det,
+                (m[0][2] * m[1][0] - m[0][0] * m[1][2]) * inv_det,
+            ],
+            [
+                (m[1][0] * m[2][1] - m[1][1] * m[2][0]) * inv_det,
+                (m[0][1] * m[2][0] - m[0][0] * m[2][1]) * inv_det,
+                (m[0][0] * m[1][1] - m[0][1] * m[1][0]) * inv_det,
+            ],
+        ]))
+    }
+}
+
+impl Mat4 {
+    pub const IDENTITY: Self = Self {
+        rows: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub fn from_rows(rows: [[f64; 4]; 4]) -> Self {
+        Self { rows }
+    }
+
+    /// Embeds a 3x3 linear transform with no translation.
+    pub fn from_mat3(m: Mat3) -> Self {
+        let r = &m.rows;
+
+        Self::from_rows([
+            [r[0][0], r[0][1], r[0][2], 0.0],
+            [r[1][0], r[1][1], r[1][2], 0.0],
+            [r[2][0], r[2][1], r[2][2], 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn from_quat(q: Quat) -> Self {
+        Self::from_mat3(Mat3::from_quat(q))
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        let mut m = Self::IDENTITY;
+        m.rows[0][3] = offset.x;
+        m.rows[1][3] = offset.y;
+        m.rows[2][3] = offset.z;
+        m
+    }
+
+    pub fn scaling(scale: Vec3) -> Self {
+        let mut m = Self::IDENTITY;
+        m.rows[0][0] = scale.x;
+        m.rows[1][1] = scale.y;
+        m.rows[2][2] = scale.z;
+        m
+    }
+
+    pub fn rotation_x(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+
+        Self::from_rows([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, c, -s, 0.0],
+            [0.0, s, c, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_y(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+
+        Self::from_rows([
+            [c, 0.0, s, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [-s, 0.0, c, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_z(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+
+        Self::from_rows([
+            [c, -s, 0.0, 0.0],
+            [s, c, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// View matrix for a camera at `eye` looking towards `target`.
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        let forward = (target - eye).normalize();
+        let right = up.cross(forward).normalize();
+        let up = forward.cross(right);
+
+        Self::from_rows([
+            [right.x, right.y, right.z, -right.dot(eye)],
+            [up.x, up.y, up.z, -up.dot(eye)],
+            [forward.x, forward.y, forward.z, -forward.dot(eye)],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Perspective projection with a vertical field of view in radians.
+    /// Clip-space `w` is view-space `z`, and depth maps `near..far` to `0..1`.
+    pub fn perspective(fov_y: f64, aspect: f64, near: f64, far: f64) -> Self {
+        let f = 1.0 / (fov_y * 0.5).tan();
+        let range = far / (far - near);
+
+        Self::from_rows([
+            [f / aspect, 0.0, 0.0, 0.0],
+            [0.0, f, 0.0, 0.0],
+            [0.0, 0.0, range, -near * range],
+            [0.0, 0.0, 1.0, 0.0],
+        ])
+    }
+
+    /// Orthographic projection of the given view-space box onto `-1..1` in
+    /// X and Y and `0..1` in depth.
+    pub fn orthographic(left: f64, right: f64, bottom: f64, top: f64, near: f64, far: f64) -> Self {
+        Self::from_rows([
+            [
+                2.0 / (right - left),
+                0.0,
+                0.0,
+                -(right + left) / (right - left),
+            ],
+            [
+                0.0,
+                2.0 / (top - bottom),
+                0.0,
+                -(top + bottom) / (top - bottom),
+            ],
+            [0.0, 0.0, 1.0 / (far - near), -near / (far - near)],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let m = &self.rows;
+        let mut rows = [[0.0; 4]; 4];
+
+        for (r, row) in rows.iter_mut().enumerate() {
+            for (c, cell) in row.iter_mut().enumerate() {
+                *cell = m[c][r];
+            }
+        }
+
+        Self { rows }
+    }
+
+    /// General inverse by cofactor expansion. Returns `None` if the matrix
+    /// is singular, judged the same way as [`Mat3::inverse`].
+    pub fn inverse(&self) -> Option<Self> {
+        let m = &self.rows;
+
+        let s0 = m[0][0] * m[1][1] - m[1][0] * m[0][1];
+        let s1 = m[0][0] * m[1][2] - m[1][0] * m[0][2];
+        let s2 = m[0][0] * m[1][3] - m[1][0] * m[0][3];
+        let s3 = m[0][1] * m[1][2] - m[1][1] * m[0][2];
+        let s4 = m[0][1] * m[1][3] - m[1][1] * m[0][3];
+        let s5 = m[0][2] * m[1][3] - m[1][2] * m[0][3];
+
+        let c5 = m[2][2] * m[3][3] - m[3][2] * m[2][3];
+        let c4 = m[2][1] * m[3][3] - m[3][1] * m[2][3];
+        let c3 = m[2][1] * m[3][2] - m[3][1] * m[2][2];
+        let c2 = m[2][0] * m[3][3] - m[3][0] * m[2][3];
+        let c1 = m[2][0] * m[3][2] - m[3][0] * m[2][2];
+        let c0 = m[2][0] * m[3][1] - m[3][0] * m[2][1];
+
+        let det = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
+        if !det.is_normal() {
+            return None;
+        }
+
+        let inv = 1.0 / det;
+
+        Some(Self::from_rows([
+            [
+                (m[1][1] * c5 - m[1][2] * c4 + m[1][3] * c3) * inv,
+                (-m[0][1] * c5 + m[0][2] * c4 - m[0][3] * c3) * inv,
+                (m[3][1] * s5 - m[3][2] * s4 + m[3][3] * s3) * inv,
+                (-m[2][1] * s5 + m[2][2] * s4 - m[2][3] * s3) * inv,
+            ],
+            [
+                (-m[1][0] * c5 + m[1][2] * c2 - m[1][3] * c1) * inv,
+                (m[0][0] * c5 - m[0][2] * c2 + m[0][3] * c1) * inv,
+                (-m[3][0] * s5 + m[3][2] * s2 - m[3][3] * s1) * inv,
+                (m[2][0] * s5 - m[2][2] * s2 + m[2][3] * s1) * inv,
+            ],
+            [
+                (m[1][0] * c4 - m[1][1] * c2 + m[1][3] * c0) * inv,
+                (-m[0][0] * c4 + m[0][1] * c2 - m[0][3] * c0) * inv,
+                (m[3][0] * s4 - m[3][1] * s2 + m[3][3] * s0) * inv,
+                (-m[2][0] * s4 + m[2][1] * s2 - m[2][3] * s0) * inv,
+            ],
+            [
+                (-m[1][0] * c3 + m[1][1] * c1 - m[1][2] * c0) * inv,
+                (m[0][0] * c3 - m[0][1] * c1 + m[0][2] * c0) * inv,
+                (-m[3][0] * s3 + m[3][1] * s1 - m[3][2] * s0) * inv,
+                (m[2][0] * s3 - m[2][1] * s1 + m[2][2] * s0) * inv,
+            ],
+        ]))
+    }
+
+    /// Transforms a point (`w = 1`), dividing by the resulting `w`.
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        let v = *self * p.extend(1.0);
+        v.truncate() / v.w
+    }
+
+    /// Transforms a direction (`w = 0`), ignoring translation.
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        (*self * v.extend(0.0)).truncate()
+    }
+}
+
+impl Mul for Mat3 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let (a, b) = (&self.rows, &rhs.rows);
+        let mut rows = [[0.0; 3]; 3];
+
+        for (r, row) in rows.iter_mut().enumerate() {
+            for (c, cell) in row.iter_mut().enumerate() {
+                *cell = (0..3).map(|k| a[r][k] * b[k][c]).sum();
+            }
+        }
+
+        Self { rows }
+    }
+}
+
+impl Mul<Vec3> for Mat3 {
+    type Output = Vec3;
+
+    fn mul(self, v: Vec3) -> Vec3 {
+        let m = &self.rows;
+
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let (a, b) = (&self.rows, &rhs.rows);
+        let mut rows = [[0.0; 4]; 4];
+
+        for (r, row) in rows.iter_mut().enumerate() {
+            for (c, cell) in row.iter_mut().enumerate() {
+                *cell = (0..4).map(|k| a[r][k] * b[k][c]).sum();
+            }
+        }
+
+        Self { rows }
+    }
+}
+
+impl Mul<Vec4> for Mat4 {
+    type Output = Vec4;
+
+    fn mul(self, v: Vec4) -> Vec4 {
+        let m = &self.rows;
+
+        Vec4::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z + m[0][3] * v.w,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z + m[1][3] * v.w,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z + m[2][3] * v.w,
+            m[3][0] * v.x + m[3][1] * v.y + m[3][2] * v.z + m[3][3] * v.w,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-9, "{a:?} != {b:?}");
+    }
+
+    fn assert_identity(m: Mat4) {
+        for (r, row) in m.rows.iter().enumerate() {
+            for (c, cell) in row.iter().enumerate() {
+                let expected = if r == c { 1.0 } else { 0.0 };
+                assert!((cell - expected).abs() < 1e-9, "{m:?} is not the identity");
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_the_matrix() {
+        let m = Mat4::translation(Vec3::new(1.0, -2.0, 3.0))
+            * Mat4::from_quat(Quat::from_euler(0.3, 1.1, -0.7))
+            * Mat4::scaling(Vec3::new(2.0, 0.5, 4.0));
+
+        assert_identity(m * m.inverse().unwrap());
+        assert_identity(m.inverse().unwrap() * m);
+    }
+
+    #[test]
+    fn inverse_of_a_tiny_scale_exists() {
+        let m = Mat4::scaling(Vec3::ONE * 1e-6);
+        assert_identity(m * m.inverse().unwrap());
+
+        let m = Mat3::from_rows([[1e-6, 0.0, 0.0], [0.0, 1e-6, 0.0], [0.0, 0.0, 1e-6]]);
+        let product = m * m.inverse().unwrap();
+        assert_close(product * Vec3::new(1.0, 2.0, 3.0), Vec3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn inverse_of_a_singular_matrix_is_none() {
+        assert!(Mat4::scaling(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+        assert!(
+            Mat3::from_rows([[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 1.0, 0.0]])
+                .inverse()
+                .is_none()
+        );
+        assert!(Mat4::scaling(Vec3::ONE * f64::NAN).inverse().is_none());
+    }
+
+    #[test]
+    fn look_at_puts_the_target_on_the_forward_axis() {
+        let eye = Vec3::new(3.0, 4.0, -5.0);
+        let target = Vec3::new(-1.0, 2.0, 6.0);
+        let view = Mat4::look_at(eye, target, Vec3::Y);
+
+        assert_close(view.transform_point(eye), Vec3::default());
+        assert_close(
+            view.transform_point(target),
+            Vec3::new(0.0, 0.0, (target - eye).length()),
+        );
+        assert!(view.transform_point(eye + Vec3::Y).y > 0.0);
+    }
+
+    #[test]
+    fn perspective_maps_near_and_far_to_zero_and_one() {
+        let projection = Mat4::perspective(FRAC_PI_2, 2.0, 0.5, 100.0);
+
+        assert_close(
+            projection.transform_point(Vec3::new(0.0, 0.0, 0.5)),
+            Vec3::new(0.0, 0.0, 0.0),
+        );
+        assert_close(
+            projection.transform_point(Vec3::new(0.0, 0.0, 100.0)),
+            Vec3::new(0.0, 0.0, 1.0),
+        );
+        // A 90 degree field of view reaches y = z, and x = 2z at aspect 2.
+        let corner = projection.transform_point(Vec3::new(20.0, 10.0, 10.0));
+        assert!((corner.x - 1.0).abs() < 1e-9 && (corner.y - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn orthographic_maps_the_box_onto_clip_space() {
+        let projection = Mat4::orthographic(-4.0, 2.0, -1.0, 3.0, 1.0, 11.0);
+
+        assert_close(
+            projection.transform_point(Vec3::new(-4.0, -1.0, 1.0)),
+            Vec3::new(-1.0, -1.0, 0.0),
+        );
+        assert_close(
+            projection.transform_point(Vec3::new(2.0, 3.0, 11.0)),
+            Vec3::new(1.0, 1.0, 1.0),
+        );
+    }
+
+    #[test]
+    fn rotations_are_left_handed() {
+        assert_close(
+            Mat4::rotation_z(FRAC_PI_2).transform_point(Vec3::X),
+            Vec3::Y,
+        );
+        assert_close(
+            Mat4::rotation_x(FRAC_PI_2).transform_point(Vec3::Y),
+            Vec3::Z,
+        );
+        assert_close(
+            Mat4::rotation_y(FRAC_PI_2).transform_point(Vec3::Z),
+            Vec3::X,
+        );
+    }
+
+    #[test]
+    fn translation_moves_points_but_not_vectors() {
+        let m = Mat4::translation(Vec3::new(1.0, 2.0, 3.0));
+
+        assert_close(m.transform_point(Vec3::ONE), Vec3::new(2.0, 3.0, 4.0));
+        assert_close(m.transform_vector(Vec3::ONE), Vec3::ONE);
+    }
+}
@@ -1,5 +1,7 @@
 pub mod colour;
 pub mod material;
+pub mod matrix;
+pub mod quaternion;
 pub mod texture;
 pub mod triangle;
 pub mod vector;
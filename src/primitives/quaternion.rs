@@ -0,0 +1,191 @@
+use std::ops::Mul;
+
+use crate::primitives::vector::Vec3;
+
+/// Unit quaternion rotation. Composition follows matrix order: `a * b`
+/// applies `b` first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quat {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub w: f64,
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Quat {
+    pub const IDENTITY: Self = Self::new(0.0, 0.0, 0.0, 1.0);
+
+    pub const fn new(x: f64, y: f64, z: f64, w: f64) -> Self {
+        Self { x, y, z, w }
+    }
+
+    /// Rotation of `angle` radians about `axis`, which need not be normalised.
+    pub fn from_axis_angle(axis: Vec3, angle: f64) -> Self {
+        let axis = axis.normalize();
+        let (s, c) = (angle * 0.5).sin_cos();
+
+        Self::new(axis.x * s, axis.y * s, axis.z * s, c)
+    }
+
+    /// Rotates about X, then Y, then Z.
+    pub fn from_euler(x: f64, y: f64, z: f64) -> Self {
+        Self::from_axis_angle(Vec3::Z, z)
+            * Self::from_axis_angle(Vec3::Y, y)
+            * Self::from_axis_angle(Vec3::X, x)
+    }
+
+    pub fn dot(self, other: Self) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn length(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(self) -> Self {
+        let inv = 1.0 / self.length();
+        Self::new(self.x * inv, self.y * inv, self.z * inv, self.w * inv)
+    }
+
+    pub fn conjugate(self) -> Self {
+        Self::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    pub fn inverse(self) -> Self {
+        let inv = 1.0 / self.dot(self);
+        let c = self.conjugate();
+        Self::new(c.x * inv, c.y * inv, c.z * inv, c.w * inv)
+    }
+
+    /// Spherical linear interpolation along the shortest arc.
+    pub fn slerp(self, other: Self, t: f64) -> Self {
+        let mut cos = self.dot(other);
+        let mut end = other;
+
+        if cos < 0.0 {
+            cos = -cos;
+            end = Self::new(-end.x, -end.y, -end.z, -end.w);
+        }
+
+        // Nearly parallel: fall back to a normalised lerp to avoid dividing
+        // by a vanishing sine.
+        let (a, b) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+
+        Self::new(
+            self.x * a + end.x * b,
+            self.y * a + end.y * b,
+            self.z * a + end.z * b,
+            self.w * a + end.w * b,
+        )
+        .normalize()
+    }
+}
+
+impl Mul for Quat {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        )
+    }
+}
+
+impl Mul<Vec3> for Quat {
+    type Output = Vec3;
+
+    fn mul(self, v: Vec3) -> Vec3 {
+        let u = Vec3::new(self.x, self.y, self.z);
+        let t = u.cross(v) * 2.0;
+
+        v + t * self.w + u.cross(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    use super::*;
+    use crate::primitives::matrix::Mat3;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-9, "{a:?} != {b:?}");
+    }
+
+    /// Rodrigues' rotation formula, independent of the quaternion maths.
+    fn rotate(axis: Vec3, angle: f64, v: Vec3) -> Vec3 {
+        let k = axis.normalize();
+        let (s, c) = angle.sin_cos();
+        v * c + k.cross(v) * s + k * (k.dot(v) * (1.0 - c))
+    }
+
+    #[test]
+    fn rotation_matches_axis_angle() {
+        let axis = Vec3::new(1.0, -2.0, 0.5);
+        let q = Quat::from_axis_angle(axis, 0.8);
+        let m = Mat3::from_quat(q);
+
+        for v in [Vec3::X, Vec3::Y, Vec3::Z, Vec3::new(3.0, 1.0, -2.0)] {
+            assert_close(q * v, rotate(axis, 0.8, v));
+            assert_close(m * v, rotate(axis, 0.8, v));
+        }
+    }
+
+    #[test]
+    fn product_applies_the_right_hand_side_first() {
+        let a = Quat::from_axis_angle(Vec3::Z, FRAC_PI_2);
+        let b = Quat::from_axis_angle(Vec3::X, FRAC_PI_2);
+
+        assert_close((a * b) * Vec3::Y, a * (b * Vec3::Y));
+        assert_close((a * b) * Vec3::Y, Vec3::Z);
+    }
+
+    #[test]
+    fn inverse_undoes_the_rotation() {
+        let q = Quat::from_euler(0.3, -1.2, 2.0);
+        let v = Vec3::new(1.0, 2.0, 3.0);
+
+        assert_close(q.inverse() * (q * v), v);
+    }
+
+    #[test]
+    fn slerp_hits_the_endpoints_and_the_halfway_angle() {
+        let a = Quat::IDENTITY;
+        let b = Quat::from_axis_angle(Vec3::Y, FRAC_PI_2);
+
+        assert_close(a.slerp(b, 0.0) * Vec3::Z, a * Vec3::Z);
+        assert_close(a.slerp(b, 1.0) * Vec3::Z, b * Vec3::Z);
+        assert_close(
+            a.slerp(b, 0.5) * Vec3::Z,
+            Quat::from_axis_angle(Vec3::Y, FRAC_PI_2 * 0.5) * Vec3::Z,
+        );
+    }
+
+    #[test]
+    fn slerp_takes_the_shortest_arc() {
+        let a = Quat::from_axis_angle(Vec3::Z, 0.1);
+        // The same rotation as 0.3 radians, with every component negated.
+        let b = Quat::from_axis_angle(Vec3::Z, 0.3 - 2.0 * PI);
+
+        assert_close(
+            a.slerp(b, 0.5) * Vec3::X,
+            Quat::from_axis_angle(Vec3::Z, 0.2) * Vec3::X,
+        );
+    }
+}
@@ -1,17 +1,39 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::primitives::colour::Colour;
 
+/// How a texture's 8-bit values relate to light.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColourSpace {
+    /// Gamma-encoded colour, e.g. diffuse maps and anything painted.
+    #[default]
+    Srgb,
+    /// Plain data such as normals or roughness, used as stored.
+    Linear,
+}
+
+/// How a texture is sampled between texel centres.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Filter {
+    #[default]
+    Nearest,
+    /// Blends the four nearest texels in linear space.
+    Bilinear,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Texture {
     pub width: usize,
     pub height: usize,
     pub data: Vec<Colour>,
+    pub path: Option<PathBuf>,
+    pub colour_space: ColourSpace,
+    pub filter: Filter,
 }
 
 impl Texture {
     #[allow(unused)]
-    pub fn from_file(path: &Path) -> Result<Self, String> {
+    pub fn from_file(path: &Path, colour_space: ColourSpace) -> Result<Self, String> {
         let img = image::open(path).map_err(|e| e.to_string())?.to_rgba8();
         let (width, height) = img.dimensions();
         let mut pixels = Vec::with_capacity((width * height) as usize);
@@ -24,10 +46,30 @@ impl Texture {
             width: width as usize,
             height: height as usize,
             data: pixels,
+            path: Some(path.to_path_buf()),
+            colour_space,
+            filter: Filter::default(),
         })
     }
 
+    /// The colour at `u`, `v` as sRGB, ready to display. Nearest sampling of
+    /// an sRGB texture returns the stored texel unchanged.
     pub fn sample(&self, u: f64, v: f64) -> Colour {
+        match (self.filter, self.colour_space) {
+            (Filter::Nearest, ColourSpace::Srgb) => self.nearest(u, v),
+            _ => Colour::from_linear(self.sample_linear(u, v)),
+        }
+    }
+
+    /// The value at `u`, `v` in linear space, for lighting and blending.
+    pub fn sample_linear(&self, u: f64, v: f64) -> [f32; 4] {
+        match self.filter {
+            Filter::Nearest => self.to_linear(self.nearest(u, v)),
+            Filter::Bilinear => self.bilinear(u, v),
+        }
+    }
+
+    fn nearest(&self, u: f64, v: f64) -> Colour {
         let u = u.clamp(0.0, 1.0);
         let v = v.clamp(0.0, 1.0);
 
@@ -36,6 +78,39 @@ impl Texture {
 
         self.data[y * self.width + x]
     }
+
+    fn bilinear(&self, u: f64, v: f64) -> [f32; 4] {
+        let x = u.clamp(0.0, 1.0) * (self.width - 1) as f64;
+        let y = v.clamp(0.0, 1.0) * (self.height - 1) as f64;
+
+        let (x0, y0) = (x as usize, y as usize);
+        let x1 = (x0 + 1).min(self.width - 1);
+        let y1 = (y0 + 1).min(self.height - 1);
+        let (tx, ty) = ((x - x0 as f64) as f32, (y - y0 as f64) as f32);
+
+        let texel = |x: usize, y: usize| self.to_linear(self.data[y * self.width + x]);
+        let (a, b) = (texel(x0, y0), texel(x1, y0));
+        let (c, d) = (texel(x0, y1), texel(x1, y1));
+
+        std::array::from_fn(|i| {
+            let top = a[i] + (b[i] - a[i]) * tx;
+            let bottom = c[i] + (d[i] - c[i]) * tx;
+            top + (bottom - top) * ty
+        })
+    }
+
+    /// Decodes a stored texel according to `colour_space`.
+    fn to_linear(&self, texel: Colour) -> [f32; 4] {
+        match self.colour_space {
+            ColourSpace::Srgb => texel.to_linear(),
+            ColourSpace::Linear => [
+                texel.red as f32 / 255.0,
+                texel.green as f32 / 255.0,
+                texel.blue as f32 / 255.0,
+                texel.alpha as f32 / 255.0,
+            ],
+        }
+    }
 }
 
 impl Texture {
@@ -58,6 +133,9 @@ impl Texture {
             width: size,
             height: size,
             data: pixels,
+            path: None,
+            colour_space: ColourSpace::Srgb,
+            filter: Filter::default(),
         }
     }
 
@@ -81,6 +159,65 @@ impl Texture {
             width: size,
             height: size,
             data: pixels,
+            path: None,
+            colour_space: ColourSpace::Srgb,
+            filter: Filter::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texture(data: Vec<Colour>, colour_space: ColourSpace, filter: Filter) -> Texture {
+        Texture {
+            width: data.len(),
+            height: 1,
+            data,
+            path: None,
+            colour_space,
+            filter,
+        }
+    }
+
+    fn grey(v: u8) -> Colour {
+        Colour::new(v, v, v, 255)
+    }
+
+    #[test]
+    fn bilinear_blends_srgb_in_linear_light() {
+        let t = texture(
+            vec![grey(0), grey(255)],
+            ColourSpace::Srgb,
+            Filter::Bilinear,
+        );
+
+        // Half the light of white, not half its encoded value.
+        assert_eq!(t.sample(0.5, 0.0), grey(188));
+        assert!((t.sample_linear(0.5, 0.0)[0] - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn bilinear_blends_linear_data_as_stored() {
+        let t = texture(
+            vec![grey(0), grey(255)],
+            ColourSpace::Linear,
+            Filter::Bilinear,
+        );
+
+        assert!((t.sample_linear(0.25, 0.0)[0] - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn nearest_returns_srgb_texels_unchanged() {
+        let data = vec![grey(10), grey(100), grey(200)];
+        let t = texture(data.clone(), ColourSpace::Srgb, Filter::Nearest);
+
+        for (i, &texel) in data.iter().enumerate() {
+            assert_eq!(t.sample(i as f64 / 2.0, 0.0), texel);
         }
+        assert_eq!(t.sample(-1.0, 0.0), grey(10));
+        assert_eq!(t.sample(2.0, 0.0), grey(200));
     }
 }
@@ -11,4 +11,15 @@ impl Vertex {
     pub fn new(x: f64, y: f64, z: f64, u: f64, v: f64) -> Self {
         Self { x, y, z, u, v }
     }
+
+    /// Interpolates every attribute linearly; `t = 0` gives `self`.
+    pub fn lerp(&self, other: &Self, t: f64) -> Self {
+        Self::new(
+            self.x + (other.x - self.x) * t,
+            self.y + (other.y - self.y) * t,
+            self.z + (other.z - self.z) * t,
+            self.u + (other.u - self.u) * t,
+            self.v + (other.v - self.v) * t,
+        )
+    }
 }
@@ -1,13 +1,91 @@
-use crate::math::is_back_facing;
+use std::mem::take;
+use std::time::Instant;
+
+use crate::camera::Camera;
+use crate::font::{GLYPH_HEIGHT, GLYPH_WIDTH, glyph};
+use crate::hdr::ToneMapper;
+use crate::math::{clip_line_depth, clip_triangle_depth, transform_vertex};
+use crate::mesh::Mesh;
+use crate::msaa::{Msaa, SampleBuffers};
 use crate::primitives::material::Material;
+use crate::primitives::matrix::Mat4;
+use crate::primitives::vector::Vec3;
 use crate::primitives::{colour::Colour, vertex::Vertex};
+use crate::scene::{NodeContent, Scene};
+use crate::state::{CullMode, DepthMode, DepthState, FrontFace, StencilState};
+use crate::stats::RenderStats;
+
+/// Fraction of its depth a line or point is pulled towards the camera, so
+/// edges drawn over their own filled faces win the depth test.
+const LINE_DEPTH_OFFSET: f64 = 1e-3;
+
+/// How mesh triangles are turned into pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PolygonMode {
+    #[default]
+    Fill,
+    /// Triangle edges only.
+    Wireframe,
+    /// Mesh vertices only.
+    Points,
+    /// Filled triangles with their edges drawn on top.
+    FillWireframe,
+}
+
+impl PolygonMode {
+    /// The mode after this one, wrapping around, for cycling with a key.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Fill => Self::Wireframe,
+            Self::Wireframe => Self::Points,
+            Self::Points => Self::FillWireframe,
+            Self::FillWireframe => Self::Fill,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Renderer {
     pub width: usize,
     pub height: usize,
     pub buffer: Vec<u8>,
+    /// Linear-light RGBA that fragments go to instead of `buffer` while
+    /// set, so colours can exceed 1. [`Renderer::resolve_hdr`] tone maps
+    /// it into `buffer`.
+    pub hdr: Option<Vec<[f32; 4]>>,
+    /// Multiplies HDR colour before tone mapping.
+    pub exposure: f32,
+    pub tone_mapper: ToneMapper,
+    /// Per-sample buffers that triangles are rasterized into while
+    /// multisampling. [`Renderer::resolve_msaa`] averages them into the
+    /// per-pixel buffers.
+    pub samples: Option<SampleBuffers>,
+    /// Stored as `depth_state.mode` encodes it.
     pub depth: Vec<f64>,
+    /// 8-bit stencil value per pixel, only used while
+    /// `stencil_state.enabled` is set.
+    pub stencil: Vec<u8>,
+    /// Counters for everything drawn since [`Renderer::begin_frame`].
+    pub stats: RenderStats,
+    /// Time the vertex, setup and raster stages into `stats.timings`.
+    pub profile_stages: bool,
+    /// Applies to every mesh drawn.
+    pub polygon_mode: PolygonMode,
+    /// Face culling for every draw, unless the material is double-sided.
+    pub cull_mode: CullMode,
+    pub front_face: FrontFace,
+    /// Depth test, write mask, bias and encoding. Call
+    /// [`Renderer::clear`] after changing the mode.
+    pub depth_state: DepthState,
+    pub stencil_state: StencilState,
+    /// Colour of wireframe edges and points.
+    pub wireframe_colour: Colour,
+    /// Side length in pixels of the square drawn for each point.
+    pub point_size: usize,
+    /// Near plane of the camera being drawn, for encoding depth.
+    depth_near: f64,
+    /// View-space vertices of the mesh being drawn, reused between draws.
+    view_vertices: Vec<Vertex>,
 }
 
 impl Renderer {
@@ -16,10 +94,45 @@ impl Renderer {
             width,
             height,
             buffer: vec![0; width * height * 4],
-            depth: vec![f64::INFINITY; width * height],
+            hdr: None,
+            exposure: 1.0,
+            tone_mapper: ToneMapper::default(),
+            samples: None,
+            depth: vec![DepthMode::default().clear_value(); width * height],
+            stencil: vec![0; width * height],
+            stats: RenderStats::default(),
+            profile_stages: false,
+            polygon_mode: PolygonMode::default(),
+            cull_mode: CullMode::default(),
+            front_face: FrontFace::default(),
+            depth_state: DepthState::default(),
+            stencil_state: StencilState::default(),
+            wireframe_colour: Colour::new(255, 160, 0, 255),
+            point_size: 3,
+            depth_near: Camera::new(width, height).near,
+            view_vertices: Vec::new(),
         }
     }
 
+    /// Reallocates every buffer at a new size, keeping the pipeline
+    /// settings. The contents are lost.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.buffer = vec![0; width * height * 4];
+        self.depth = vec![self.depth_state.mode.clear_value(); width * height];
+        self.stencil = vec![0; width * height];
+        self.set_hdr(self.hdr.is_some());
+        self.set_msaa(self.msaa());
+    }
+
+    /// Resets `stats` so they describe the frame about to be drawn.
+    pub fn begin_frame(&mut self) {
+        self.stats = RenderStats::default();
+    }
+
+    /// Fills the colour buffers with `colour`, empties the depth buffer and
+    /// zeroes the stencil buffer.
     pub fn clear(&mut self, colour: Colour) {
         for px in self.buffer.chunks_exact_mut(4) {
             px[0] = colour.red;
@@ -28,55 +141,287 @@ impl Renderer {
             px[3] = colour.alpha;
         }
 
-        for depth in self.depth.iter_mut() {
-            *depth = f64::INFINITY;
+        if let Some(hdr) = &mut self.hdr {
+            hdr.fill(colour.to_linear());
+        }
+
+        self.depth.fill(self.depth_state.mode.clear_value());
+        self.stencil.fill(0);
+
+        if let Some(samples) = &mut self.samples {
+            samples.colour.fill(colour.to_linear());
+            samples.depth.fill(self.depth_state.mode.clear_value());
+            samples.stencil.fill(0);
+        }
+    }
+
+    /// Turns the HDR colour buffer on or off. A new buffer starts black.
+    pub fn set_hdr(&mut self, enabled: bool) {
+        self.hdr = enabled.then(|| vec![[0.0, 0.0, 0.0, 1.0]; self.width * self.height]);
+    }
+
+    /// Applies exposure and the tone mapper to the HDR buffer and writes the
+    /// result to `buffer` as sRGB. Does nothing without an HDR buffer.
+    pub fn resolve_hdr(&mut self) {
+        let Some(hdr) = &self.hdr else {
+            return;
+        };
+
+        for (out, px) in self.buffer.chunks_exact_mut(4).zip(hdr) {
+            let mapped = px.map(|c| self.tone_mapper.map(c * self.exposure));
+            let colour = Colour::from_linear([mapped[0], mapped[1], mapped[2], px[3]]);
+
+            out[0] = colour.red;
+            out[1] = colour.green;
+            out[2] = colour.blue;
+            out[3] = colour.alpha;
+        }
+    }
+
+    /// The current multisampling setting.
+    pub fn msaa(&self) -> Msaa {
+        self.samples.as_ref().map_or(Msaa::Off, |s| s.msaa)
+    }
+
+    /// Changes the number of samples per pixel. New sample buffers start
+    /// black and empty.
+    pub fn set_msaa(&mut self, msaa: Msaa) {
+        let clear = self.depth_state.mode.clear_value();
+        self.samples =
+            (msaa != Msaa::Off).then(|| SampleBuffers::new(msaa, self.width * self.height, clear));
+    }
+
+    /// Averages each pixel's samples into the HDR buffer if there is one,
+    /// or `buffer` otherwise. Depth keeps the nearest sample and stencil
+    /// the first. Does nothing without multisampling.
+    pub fn resolve_msaa(&mut self) {
+        let Some(samples) = &self.samples else {
+            return;
+        };
+
+        let n = samples.msaa.samples();
+        let nearer = self.depth_state.mode.nearer();
+
+        for i in 0..self.width * self.height {
+            let range = i * n..(i + 1) * n;
+
+            let mut sum = [0.0; 4];
+            for px in &samples.colour[range.clone()] {
+                for c in 0..4 {
+                    sum[c] += px[c];
+                }
+            }
+            let average = sum.map(|c| c / n as f32);
+
+            if let Some(hdr) = &mut self.hdr {
+                hdr[i] = average;
+            } else {
+                let colour = Colour::from_linear(average);
+                self.buffer[i * 4..i * 4 + 4].copy_from_slice(&[
+                    colour.red,
+                    colour.green,
+                    colour.blue,
+                    colour.alpha,
+                ]);
+            }
+
+            self.depth[i] = samples.depth[range.clone()]
+                .iter()
+                .copied()
+                .reduce(|best, d| if nearer.passes(d, best) { d } else { best })
+                .unwrap_or(self.depth[i]);
+            self.stencil[i] = samples.stencil[range.start];
+        }
+    }
+
+    /// Finishes a frame: resolves multisampling, then HDR, leaving the
+    /// result in `buffer`.
+    pub fn resolve(&mut self) {
+        self.resolve_msaa();
+        self.resolve_hdr();
+    }
+
+    /// Near plane of the camera last drawn with, which
+    /// [`DepthMode::decode`] needs to turn `depth` back into distances.
+    pub fn depth_near(&self) -> f64 {
+        self.depth_near
+    }
+
+    /// Sets every stencil value, leaving colour and depth alone.
+    pub fn clear_stencil(&mut self, value: u8) {
+        self.stencil.fill(value);
+        if let Some(samples) = &mut self.samples {
+            samples.stencil.fill(value);
         }
     }
 }
 
 impl Renderer {
+    /// Stencil and depth tests a fragment at view-space depth `z` and
+    /// writes it if both pass, following `stencil_state` and `depth_state`.
     pub fn put_pixel_depth(&mut self, x: usize, y: usize, z: f64, colour: Colour) {
+        self.put_fragment(x, y, z, colour, Vec3::default());
+    }
+
+    /// [`Renderer::put_pixel_depth`] with linear `emissive` light added on
+    /// top of `colour`.
+    fn put_fragment(&mut self, x: usize, y: usize, z: f64, colour: Colour, emissive: Vec3) {
+        if self.hdr.is_some() || self.samples.is_some() || emissive != Vec3::default() {
+            self.put_fragment_linear(x, y, z, shade_linear(colour, emissive));
+            return;
+        }
+
+        // Unlit colours for the 8-bit buffer skip the round trip through
+        // linear, so they are written exactly and cheaply.
         if x >= self.width || y >= self.height {
             return;
         }
 
         let idx = y * self.width + x;
+        if self.depth_stencil_test(idx, z) {
+            self.stats.fragments_written += 1;
+            self.write_colour(idx, colour);
+        }
+    }
 
-        if z < self.depth[idx] {
-            self.depth[idx] = z;
+    /// Tests and writes a fragment with a linear-light colour: into the HDR
+    /// buffer if there is one, or encoded as sRGB into `buffer`. While
+    /// multisampling, every sample of the pixel is tested and written.
+    fn put_fragment_linear(&mut self, x: usize, y: usize, z: f64, linear: [f32; 4]) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
 
-            let base = idx * 4;
-            self.buffer[base] = colour.red;
-            self.buffer[base + 1] = colour.green;
-            self.buffer[base + 2] = colour.blue;
-            self.buffer[base + 3] = colour.alpha;
+        let idx = y * self.width + x;
+
+        if let Some(n) = self.samples.as_ref().map(|s| s.msaa.samples()) {
+            for i in idx * n..(idx + 1) * n {
+                self.put_sample(i, z, linear);
+            }
+            return;
+        }
+
+        if !self.depth_stencil_test(idx, z) {
+            return;
+        }
+
+        self.stats.fragments_written += 1;
+        match &mut self.hdr {
+            Some(hdr) => hdr[idx] = linear,
+            None => self.write_colour(idx, Colour::from_linear(linear)),
+        }
+    }
+
+    fn write_colour(&mut self, idx: usize, colour: Colour) {
+        let base = idx * 4;
+        self.buffer[base] = colour.red;
+        self.buffer[base + 1] = colour.green;
+        self.buffer[base + 2] = colour.blue;
+        self.buffer[base + 3] = colour.alpha;
+    }
+
+    /// Tests a fragment against sample `i` of the sample buffers and
+    /// writes its linear colour there if it passes.
+    fn put_sample(&mut self, i: usize, z: f64, linear: [f32; 4]) {
+        if self.depth_stencil_test(i, z)
+            && let Some(samples) = &mut self.samples
+        {
+            samples.colour[i] = linear;
+            self.stats.fragments_written += 1;
         }
     }
 
+    /// Runs the stencil and depth tests for a fragment at view-space depth
+    /// `z` against entry `i` of the depth and stencil buffers, or of the
+    /// sample buffers while multisampling. Updates both as the states say
+    /// and returns whether the colour should be written.
+    fn depth_stencil_test(&mut self, i: usize, z: f64) -> bool {
+        let (depth_buffer, stencil_buffer) = match &mut self.samples {
+            Some(samples) => (&mut samples.depth[i], &mut samples.stencil[i]),
+            None => (&mut self.depth[i], &mut self.stencil[i]),
+        };
+        let stencil = self.stencil_state;
+        self.stats.fragments_tested += 1;
+
+        if stencil.enabled && !stencil.test(*stencil_buffer) {
+            *stencil_buffer = stencil.update(*stencil_buffer, stencil.fail);
+            return false;
+        }
+
+        let state = self.depth_state;
+        let depth = state.mode.encode(z + state.bias, self.depth_near);
+
+        if !state.compare.passes(depth, *depth_buffer) {
+            if stencil.enabled {
+                *stencil_buffer = stencil.update(*stencil_buffer, stencil.depth_fail);
+            }
+            return false;
+        }
+
+        if stencil.enabled {
+            *stencil_buffer = stencil.update(*stencil_buffer, stencil.pass);
+        }
+
+        self.stats.fragments_passed_depth += 1;
+        if state.write {
+            *depth_buffer = depth;
+        }
+
+        true
+    }
+
     pub fn fill_triangle(&mut self, v0: &Vertex, v1: &Vertex, v2: &Vertex, m: &Material) {
-        if is_back_facing(v0, v1, v2) {
+        self.stats.triangles_submitted += 1;
+
+        if self.is_culled(v0, v1, v2, m) {
+            self.stats.triangles_culled += 1;
+            return;
+        }
+
+        self.stats.triangles_rasterized += 1;
+        self.rasterize_triangle(v0, v1, v2, m);
+    }
+
+    /// Whether face culling drops a screen-space triangle drawn with `m`.
+    fn is_culled(&self, v0: &Vertex, v1: &Vertex, v2: &Vertex, m: &Material) -> bool {
+        let mode = if m.double_sided {
+            CullMode::None
+        } else {
+            self.cull_mode
+        };
+
+        mode.culls(self.front_face.is_back_facing(v0, v1, v2))
+    }
+
+    /// Fills a screen-space triangle without culling. Vertex `z` is view
+    /// depth, used for the depth test and perspective-correct texturing.
+    fn rasterize_triangle(&mut self, v0: &Vertex, v1: &Vertex, v2: &Vertex, m: &Material) {
+        if let Some(msaa) = self.samples.as_ref().map(|s| s.msaa) {
+            self.rasterize_triangle_msaa(v0, v1, v2, m, msaa);
             return;
         }
 
-        let x0 = v0.x as i32;
-        let y0 = v0.y as i32;
-        let x1 = v1.x as i32;
-        let y1 = v1.y as i32;
-        let x2 = v2.x as i32;
-        let y2 = v2.y as i32;
+        // Corners snap to whole pixels. Edge functions are evaluated in
+        // f64, which is exact for these whole numbers on any real screen and
+        // cannot overflow on the far off-screen corners that near-plane
+        // clipping produces.
+        let (x0, y0) = (v0.x.trunc(), v0.y.trunc());
+        let (x1, y1) = (v1.x.trunc(), v1.y.trunc());
+        let (x2, y2) = (v2.x.trunc(), v2.y.trunc());
 
-        let min_x = x0.min(x1).min(x2).clamp(0, self.width as i32 - 1);
-        let max_x = x0.max(x1).max(x2).clamp(0, self.width as i32 - 1);
-        let min_y = y0.min(y1).min(y2).clamp(0, self.height as i32 - 1);
-        let max_y = y0.max(y1).max(y2).clamp(0, self.height as i32 - 1);
+        let (right, bottom) = (self.width as f64 - 1.0, self.height as f64 - 1.0);
+        let min_x = x0.min(x1).min(x2).clamp(0.0, right) as usize;
+        let max_x = x0.max(x1).max(x2).clamp(0.0, right) as usize;
+        let min_y = y0.min(y1).min(y2).clamp(0.0, bottom) as usize;
+        let max_y = y0.max(y1).max(y2).clamp(0.0, bottom) as usize;
 
         #[inline]
-        fn edge(ax: i32, ay: i32, bx: i32, by: i32, px: i32, py: i32) -> i32 {
+        fn edge(ax: f64, ay: f64, bx: f64, by: f64, px: f64, py: f64) -> f64 {
             (px - ax) * (by - ay) - (py - ay) * (bx - ax)
         }
 
         let area = edge(x0, y0, x1, y1, x2, y2);
-        if area == 0 {
+        if area == 0.0 {
             return;
         }
 
@@ -94,14 +439,15 @@ impl Renderer {
 
         for y in min_y..=max_y {
             for x in min_x..=max_x {
-                let w0 = edge(x1, y1, x2, y2, x, y);
-                let w1 = edge(x2, y2, x0, y0, x, y);
-                let w2 = edge(x0, y0, x1, y1, x, y);
+                let (px, py) = (x as f64, y as f64);
+                let w0 = edge(x1, y1, x2, y2, px, py);
+                let w1 = edge(x2, y2, x0, y0, px, py);
+                let w2 = edge(x0, y0, x1, y1, px, py);
 
-                if (w0 >= 0 && w1 >= 0 && w2 >= 0) || (w0 <= 0 && w1 <= 0 && w2 <= 0) {
-                    let a = w0 as f64 / area as f64;
-                    let b = w1 as f64 / area as f64;
-                    let c = w2 as f64 / area as f64;
+                if (w0 >= 0.0 && w1 >= 0.0 && w2 >= 0.0) || (w0 <= 0.0 && w1 <= 0.0 && w2 <= 0.0) {
+                    let a = w0 / area;
+                    let b = w1 / area;
+                    let c = w2 / area;
 
                     let u_over_z = a * u0z + b * u1z + c * u2z;
                     let v_over_z = a * v0z + b * v1z + c * v2z;
@@ -112,15 +458,1245 @@ impl Renderer {
 
                     let depth = 1.0 / one_over_z;
 
-                    let colour = if let Some(t) = &m.kd_texture {
-                        t.sample(u, v)
+                    self.stats.pixels_shaded += 1;
+
+                    // Filtered texels stay in linear light until they are
+                    // stored, unless the 8-bit buffer is the only place
+                    // they go, where sRGB is what gets written anyway.
+                    if self.hdr.is_some() || m.emissive != Vec3::default() {
+                        let linear = shade_material(m, u, v);
+                        self.put_fragment_linear(x, y, depth, linear);
                     } else {
-                        m.diffuse
-                    };
+                        let colour = match &m.kd_texture {
+                            Some(t) => t.sample(u, v),
+                            None => m.diffuse,
+                        };
+                        self.put_fragment(x, y, depth, colour, Vec3::default());
+                    }
+                }
+            }
+        }
+    }
 
+    /// [`Renderer::rasterize_triangle`] into the sample buffers. Coverage
+    /// and depth are found at each sample position, but the triangle is
+    /// shaded once per pixel, at the centroid of the covered samples.
+    fn rasterize_triangle_msaa(
+        &mut self,
+        v0: &Vertex,
+        v1: &Vertex,
+        v2: &Vertex,
+        m: &Material,
+        msaa: Msaa,
+    ) {
+        #[inline]
+        fn edge(a: &Vertex, b: &Vertex, px: f64, py: f64) -> f64 {
+            (px - a.x) * (b.y - a.y) - (py - a.y) * (b.x - a.x)
+        }
+
+        let area = edge(v0, v1, v2.x, v2.y);
+        if area == 0.0 {
+            return;
+        }
+
+        let max_x = self.width as f64 - 1.0;
+        let max_y = self.height as f64 - 1.0;
+        let min_x = v0.x.min(v1.x).min(v2.x).floor().clamp(0.0, max_x) as usize;
+        let max_x = v0.x.max(v1.x).max(v2.x).ceil().clamp(0.0, max_x) as usize;
+        let min_y = v0.y.min(v1.y).min(v2.y).floor().clamp(0.0, max_y) as usize;
+        let max_y = v0.y.max(v1.y).max(v2.y).ceil().clamp(0.0, max_y) as usize;
+
+        let positions = msaa.positions();
+        let n = positions.len();
+
+        // Barycentric weights at a screen position, or None outside.
+        let weights = |px: f64, py: f64| {
+            let a = edge(v1, v2, px, py) / area;
+            let b = edge(v2, v0, px, py) / area;
+            let c = edge(v0, v1, px, py) / area;
+            (a >= 0.0 && b >= 0.0 && c >= 0.0).then_some((a, b, c))
+        };
+
+        let (o0z, o1z, o2z) = (1.0 / v0.z, 1.0 / v1.z, 1.0 / v2.z);
+        let mut covered = [None; 8];
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let (cx, cy) = (x as f64 + 0.5, y as f64 + 0.5);
+
+                let (mut sx, mut sy, mut count) = (0.0, 0.0, 0);
+                for (s, &(dx, dy)) in positions.iter().enumerate() {
+                    covered[s] = weights(cx + dx, cy + dy);
+                    if covered[s].is_some() {
+                        sx += dx;
+                        sy += dy;
+                        count += 1;
+                    }
+                }
+
+                if count == 0 {
+                    continue;
+                }
+
+                // The centroid of covered samples is always inside the
+                // triangle, so attributes are never extrapolated.
+                let centroid = (cx + sx / count as f64, cy + sy / count as f64);
+                let (a, b, c) =
+                    weights(centroid.0, centroid.1).unwrap_or((1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0));
+
+                let one_over_z = a * o0z + b * o1z + c * o2z;
+                let u = (a * v0.u * o0z + b * v1.u * o1z + c * v2.u * o2z) / one_over_z;
+                let v = (a * v0.v * o0z + b * v1.v * o1z + c * v2.v * o2z) / one_over_z;
+
+                self.stats.pixels_shaded += 1;
+                let linear = shade_material(m, u, v);
+                let base = (y * self.width + x) * n;
+
+                for (s, weights) in covered[..n].iter().enumerate() {
+                    if let Some((a, b, c)) = *weights {
+                        let depth = 1.0 / (a * o0z + b * o1z + c * o2z);
+                        self.put_sample(base + s, depth, linear);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draws a screen-space segment whose vertex `z` is view depth, depth
+    /// tested and trimmed to the screen.
+    fn rasterize_line(&mut self, a: &Vertex, b: &Vertex, colour: Colour) {
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let (width, height) = (self.width as f64, self.height as f64);
+
+        // Liang-Barsky against the screen rectangle.
+        let mut t0: f64 = 0.0;
+        let mut t1: f64 = 1.0;
+
+        for (p, q) in [
+            (-dx, a.x),
+            (dx, width - a.x),
+            (-dy, a.y),
+            (dy, height - a.y),
+        ] {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return;
+                }
+            } else if p < 0.0 {
+                t0 = t0.max(q / p);
+            } else {
+                t1 = t1.min(q / p);
+            }
+        }
+
+        if t0 > t1 {
+            return;
+        }
+
+        // One pixel per column or row along the major axis, each sampled at
+        // its centre (or the nearest end of the segment), so no pixel is
+        // skipped or drawn twice.
+        let x_major = dx.abs() >= dy.abs();
+        let (start, delta) = if x_major { (a.x, dx) } else { (a.y, dy) };
+        let (from, to) = (start + delta * t0, start + delta * t1);
+        let (low, high) = (from.min(to), from.max(to));
+        let (inv_a, inv_b) = (1.0 / a.z, 1.0 / b.z);
+
+        for major in low.floor() as i64..=high.floor() as i64 {
+            let t = if delta == 0.0 {
+                t0
+            } else {
+                ((major as f64 + 0.5).clamp(low, high) - start) / delta
+            };
+
+            // Depth is interpolated as 1/z, which is linear in screen space.
+            let depth = 1.0 / (inv_a + (inv_b - inv_a) * t);
+            let (x, y) = (a.x + dx * t, a.y + dy * t);
+
+            self.put_pixel_depth(
+                x as usize,
+                y as usize,
+                depth * (1.0 - LINE_DEPTH_OFFSET),
+                colour,
+            );
+        }
+    }
+
+    /// Draws a `point_size` square centred on a screen-space vertex.
+    fn rasterize_point(&mut self, v: &Vertex, colour: Colour) {
+        let half = (self.point_size / 2) as i32;
+        let depth = v.z * (1.0 - LINE_DEPTH_OFFSET);
+        let (cx, cy) = (v.x as i32, v.y as i32);
+
+        for y in cy - half..cy - half + self.point_size as i32 {
+            for x in cx - half..cx - half + self.point_size as i32 {
+                if x >= 0 && y >= 0 {
                     self.put_pixel_depth(x as usize, y as usize, depth, colour);
                 }
             }
         }
     }
 }
+
+impl Renderer {
+    /// Where geometry is clipped away from the camera. Reversed-Z has no far
+    /// plane.
+    fn far_plane(&self, camera: &Camera) -> f64 {
+        match self.depth_state.mode {
+            DepthMode::Linear => camera.far,
+            DepthMode::ReversedZ => f64::INFINITY,
+        }
+    }
+
+    /// Draws a world-space line as seen by `camera`, clipped to the near
+    /// and far planes and depth tested against what is already drawn.
+    pub fn draw_line(&mut self, from: Vec3, to: Vec3, camera: &Camera, colour: Colour) {
+        let view = camera.view_matrix();
+        let projection = camera.projection_matrix();
+        let to_view = |p: Vec3| {
+            let v = view.transform_point(p);
+            Vertex::new(v.x, v.y, v.z, 0.0, 0.0)
+        };
+
+        let far = self.far_plane(camera);
+        let Some((a, b)) = clip_line_depth(to_view(from), to_view(to), camera.near, far) else {
+            return;
+        };
+
+        self.depth_near = camera.near;
+
+        self.rasterize_line(
+            &camera.view_to_screen(&projection, &a),
+            &camera.view_to_screen(&projection, &b),
+            colour,
+        );
+    }
+
+    /// Draws `mesh` as seen by `camera`. `model` places the mesh's own
+    /// transform in the world, e.g. a scene node's world matrix.
+    pub fn draw_mesh(&mut self, mesh: &Mesh, model: &Mat4, camera: &Camera) {
+        self.draw_mesh_with_material(mesh, model, camera, None);
+    }
+
+    /// Draws every mesh instance in `scene` as seen by `camera`.
+    pub fn draw_scene(&mut self, scene: &Scene, camera: &Camera) {
+        scene.traverse(|_, node, world| {
+            if let NodeContent::Mesh(instance) = &node.content {
+                self.draw_mesh_with_material(
+                    scene.mesh(instance.mesh),
+                    world,
+                    camera,
+                    instance.material.as_deref(),
+                );
+            }
+        });
+    }
+
+    /// Like [`Renderer::draw_mesh`], but `material`, if given, replaces
+    /// every material of the mesh.
+    pub fn draw_mesh_with_material(
+        &mut self,
+        mesh: &Mesh,
+        model: &Mat4,
+        camera: &Camera,
+        material: Option<&Material>,
+    ) {
+        let profile = self.profile_stages;
+        let vertex_start = profile.then(Instant::now);
+
+        let model_view = camera.view_matrix() * *model * mesh.transform.model_matrix();
+        let projection = camera.projection_matrix();
+        let far = self.far_plane(camera);
+        self.depth_near = camera.near;
+
+        // Vertex stage: every vertex is transformed once per draw.
+        let mut view_vertices = take(&mut self.view_vertices);
+        view_vertices.clear();
+        view_vertices.extend(
+            mesh.vertices
+                .iter()
+                .map(|v| transform_vertex(&model_view, v)),
+        );
+
+        if let Some(start) = vertex_start {
+            self.stats.timings.vertex += start.elapsed();
+        }
+
+        if self.polygon_mode == PolygonMode::Points {
+            let colour = self.wireframe_colour;
+            for v in &view_vertices {
+                if v.z >= camera.near && v.z <= far {
+                    self.rasterize_point(&camera.view_to_screen(&projection, v), colour);
+                }
+            }
+
+            self.view_vertices = view_vertices;
+            return;
+        }
+
+        self.stats.triangles_submitted += mesh.triangles.len() as u64;
+
+        // Setup and raster interleave per triangle, so setup is whatever the
+        // loop spends outside the rasterizer.
+        let loop_start = profile.then(Instant::now);
+        let raster_before = self.stats.timings.raster;
+
+        for triangle in &mesh.triangles {
+            let m = material.unwrap_or(&mesh.materials[triangle.material_index]);
+            let corners = [
+                view_vertices[triangle.i0],
+                view_vertices[triangle.i1],
+                view_vertices[triangle.i2],
+            ];
+
+            let (clipped, count) = clip_triangle_depth(corners, camera.near, far);
+
+            if count < 3 {
+                continue;
+            }
+
+            if corners.iter().any(|v| v.z < camera.near || v.z > far) {
+                self.stats.triangles_clipped += 1;
+            }
+
+            let screen = clipped.map(|v| camera.view_to_screen(&projection, &v));
+            let mut visible = false;
+
+            // Clipping keeps the polygon convex and its winding intact, so a
+            // fan from the first vertex covers it.
+            for i in 1..count - 1 {
+                let (v0, v1, v2) = (&screen[0], &screen[i], &screen[i + 1]);
+
+                if self.is_culled(v0, v1, v2, m) {
+                    continue;
+                }
+
+                visible = true;
+
+                if self.polygon_mode != PolygonMode::Wireframe {
+                    let raster_start = profile.then(Instant::now);
+                    self.rasterize_triangle(v0, v1, v2, m);
+                    if let Some(start) = raster_start {
+                        self.stats.timings.raster += start.elapsed();
+                    }
+                }
+            }
+
+            // Counted per mesh triangle, however many pieces clipping made.
+            if visible {
+                self.stats.triangles_rasterized += 1;
+            } else {
+                self.stats.triangles_culled += 1;
+            }
+
+            // Edges follow the clipped polygon rather than the fan, so
+            // clipping does not add diagonals across the face.
+            if visible && self.polygon_mode != PolygonMode::Fill {
+                let raster_start = profile.then(Instant::now);
+                let colour = self.wireframe_colour;
+                for i in 0..count {
+                    self.rasterize_line(&screen[i], &screen[(i + 1) % count], colour);
+                }
+                if let Some(start) = raster_start {
+                    self.stats.timings.raster += start.elapsed();
+                }
+            }
+        }
+
+        if let Some(start) = loop_start {
+            let raster = self.stats.timings.raster - raster_before;
+            self.stats.timings.setup += start.elapsed().saturating_sub(raster);
+        }
+
+        self.view_vertices = view_vertices;
+    }
+}
+
+/// `colour` in linear light with `emissive` added.
+fn shade_linear(colour: Colour, emissive: Vec3) -> [f32; 4] {
+    add_emissive(colour.to_linear(), emissive)
+}
+
+/// The linear-light colour of `m` at texture coordinates `u`, `v`,
+/// including its emission.
+fn shade_material(m: &Material, u: f64, v: f64) -> [f32; 4] {
+    match &m.kd_texture {
+        Some(t) => add_emissive(t.sample_linear(u, v), m.emissive),
+        None => shade_linear(m.diffuse, m.emissive),
+    }
+}
+
+fn add_emissive(mut linear: [f32; 4], emissive: Vec3) -> [f32; 4] {
+    linear[0] += emissive.x as f32;
+    linear[1] += emissive.y as f32;
+    linear[2] += emissive.z as f32;
+    linear
+}
+
+/// Screen-space overlay drawing, meant for after the 3D pass. None of it
+/// reads or writes depth, and colours are blended by their alpha.
+impl Renderer {
+    /// Blends `colour` over the pixel at (`x`, `y`) by its alpha. Off-screen
+    /// pixels are ignored.
+    pub fn blend_pixel(&mut self, x: i32, y: i32, colour: Colour) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+
+        // Blended in linear light, so half-transparent black darkens by
+        // half the light rather than half the encoded value.
+        let base = (y as usize * self.width + x as usize) * 4;
+        let px = &mut self.buffer[base..base + 4];
+        let dst = Colour::new(px[0], px[1], px[2], px[3]).to_linear();
+        let src = colour.to_linear();
+        let a = src[3];
+
+        let out = Colour::from_linear(std::array::from_fn(|i| {
+            let s = if i == 3 { 1.0 } else { src[i] };
+            s * a + dst[i] * (1.0 - a)
+        }));
+
+        px.copy_from_slice(&[out.red, out.green, out.blue, out.alpha]);
+    }
+
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: usize, height: usize, colour: Colour) {
+        let x_end = (x + width as i32).min(self.width as i32);
+        let y_end = (y + height as i32).min(self.height as i32);
+
+        for py in y.max(0)..y_end {
+            for px in x.max(0)..x_end {
+                self.blend_pixel(px, py, colour);
+            }
+        }
+    }
+
+    /// Draws a one pixel wide line between two screen positions, both ends
+    /// included.
+    pub fn draw_line_2d(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, colour: Colour) {
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+
+        let (mut x, mut y) = (x0, y0);
+        let mut err = dx + dy;
+
+        loop {
+            self.blend_pixel(x, y, colour);
+
+            if x == x1 && y == y1 {
+                break;
+            }
+
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Draws `text` in the built-in 8x8 font with its top-left corner at
+    /// (`x`, `y`). `\n` starts a new line.
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, colour: Colour) {
+        for (row, line) in text.lines().enumerate() {
+            let top = y + (row * GLYPH_HEIGHT) as i32;
+
+            for (column, c) in line.chars().enumerate() {
+                let left = x + (column * GLYPH_WIDTH) as i32;
+
+                for (gy, bits) in glyph(c).iter().enumerate() {
+                    for gx in 0..GLYPH_WIDTH {
+                        if bits >> gx & 1 == 1 {
+                            self.blend_pixel(left + gx as i32, top + gy as i32, colour);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::clip_triangle_depth,
+        primitives::{
+            texture::{ColourSpace, Filter, Texture},
+            triangle::Triangle,
+        },
+        scene::{Instance, NodeContent, Scene},
+        state::{CompareFunc, CullMode, DepthState, FrontFace, StencilOp, StencilState},
+        transform::Transform,
+    };
+
+    const BACKGROUND: Colour = Colour::new(0, 0, 0, 255);
+    const RED: Colour = Colour::new(255, 0, 0, 255);
+    const BLUE: Colour = Colour::new(0, 0, 255, 255);
+
+    fn renderer(size: usize) -> Renderer {
+        let mut renderer = Renderer::new(size, size);
+        renderer.clear(BACKGROUND);
+        renderer
+    }
+
+    fn pixel(renderer: &Renderer, x: usize, y: usize) -> Colour {
+        let i = (y * renderer.width + x) * 4;
+        let px = &renderer.buffer[i..i + 4];
+        Colour::new(px[0], px[1], px[2], px[3])
+    }
+
+    /// Every pixel of `colour`, row by row.
+    fn pixels_of(renderer: &Renderer, colour: Colour) -> Vec<(usize, usize)> {
+        (0..renderer.height)
+            .flat_map(|y| (0..renderer.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| pixel(renderer, x, y) == colour)
+            .collect()
+    }
+
+    fn at(x: f64, y: f64, z: f64) -> Vertex {
+        Vertex::new(x, y, z, 0.0, 0.0)
+    }
+
+    /// A mesh of `triangles` over `vertices`, all drawn with `material`.
+    fn mesh(
+        vertices: Vec<Vertex>,
+        triangles: &[(usize, usize, usize)],
+        material: Material,
+    ) -> Mesh {
+        Mesh {
+            vertices,
+            triangles: triangles
+                .iter()
+                .map(|&(a, b, c)| Triangle::new(a, b, c, 0))
+                .collect(),
+            transform: Default::default(),
+            materials: vec![material],
+            sources: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn triangles_through_the_near_plane_fill_the_screen_below_them() {
+        // A floor passing under the camera clips to corners far off screen.
+        let floor = mesh(
+            vec![
+                at(-500.0, -1.0, -500.0),
+                at(500.0, -1.0, -500.0),
+                at(500.0, -1.0, 500.0),
+                at(-500.0, -1.0, 500.0),
+            ],
+            &[(0, 1, 2), (0, 2, 3)],
+            Material {
+                diffuse: RED,
+                ..Material::default()
+            },
+        );
+        let camera = Camera::new(600, 600);
+
+        let mut r = renderer(600);
+        r.cull_mode = CullMode::None;
+        r.draw_mesh(&floor, &Mat4::IDENTITY, &camera);
+
+        // The far edge of the floor is just below the horizon at row 300.
+        let lit = pixels_of(&r, RED);
+        assert!(lit.iter().all(|&(_, y)| y >= 300));
+        assert!(lit.len() >= 600 * 298, "{} pixels lit", lit.len());
+    }
+
+    #[test]
+    fn culling_follows_the_mode_the_winding_and_the_material() {
+        let clockwise = [at(0.0, 0.0, 1.0), at(8.0, 0.0, 1.0), at(0.0, 8.0, 1.0)];
+        let counter_clockwise = [clockwise[0], clockwise[2], clockwise[1]];
+        let single = Material {
+            diffuse: RED,
+            ..Material::default()
+        };
+        let double = Material {
+            double_sided: true,
+            ..single.clone()
+        };
+
+        // Which of the clockwise and counter-clockwise triangles are drawn.
+        let cases = [
+            (CullMode::Back, FrontFace::Clockwise, &single, (true, false)),
+            (
+                CullMode::Front,
+                FrontFace::Clockwise,
+                &single,
+                (false, true),
+            ),
+            (CullMode::None, FrontFace::Clockwise, &single, (true, true)),
+            (
+                CullMode::Back,
+                FrontFace::CounterClockwise,
+                &single,
+                (false, true),
+            ),
+            (
+                CullMode::Front,
+                FrontFace::CounterClockwise,
+                &single,
+                (true, false),
+            ),
+            (CullMode::Back, FrontFace::Clockwise, &double, (true, true)),
+            (
+                CullMode::Front,
+                FrontFace::CounterClockwise,
+                &double,
+                (true, true),
+            ),
+        ];
+
+        for (cull_mode, front_face, m, expected) in cases {
+            let drawn = |[a, b, c]: [Vertex; 3]| {
+                let mut r = renderer(8);
+                r.cull_mode = cull_mode;
+                r.front_face = front_face;
+                r.fill_triangle(&a, &b, &c, m);
+                !pixels_of(&r, RED).is_empty()
+            };
+
+            assert_eq!(
+                (drawn(clockwise), drawn(counter_clockwise)),
+                expected,
+                "{cull_mode:?} {front_face:?} double-sided: {}",
+                m.double_sided
+            );
+        }
+    }
+
+    #[test]
+    fn triangle_counters_count_mesh_triangles() {
+        let mesh = mesh(
+            vec![
+                // Facing the camera.
+                at(-1.0, -1.0, 4.0),
+                at(-1.0, 1.0, 4.0),
+                at(1.0, -1.0, 4.0),
+                // Facing away.
+                at(-1.0, -1.0, 5.0),
+                at(1.0, -1.0, 5.0),
+                at(-1.0, 1.0, 5.0),
+                // Facing the camera, with one corner behind it, so clipping
+                // leaves a quad drawn as two pieces.
+                at(-1.0, -1.0, -2.0),
+                at(-1.0, 1.0, 3.0),
+                at(1.0, -1.0, 3.0),
+                // Entirely behind the camera.
+                at(-1.0, -1.0, -4.0),
+                at(-1.0, 1.0, -4.0),
+                at(1.0, -1.0, -4.0),
+            ],
+            &[(0, 1, 2), (3, 4, 5), (6, 7, 8), (9, 10, 11)],
+            Material::default(),
+        );
+
+        let mut r = renderer(32);
+        r.draw_mesh(&mesh, &Mat4::IDENTITY, &Camera::new(32, 32));
+
+        let stats = r.stats;
+        assert_eq!(stats.triangles_submitted, 4);
+        assert_eq!(stats.triangles_culled, 1);
+        assert_eq!(stats.triangles_clipped, 1);
+        assert_eq!(stats.triangles_rasterized, 2);
+    }
+
+    #[test]
+    fn fragment_counters_follow_the_depth_test_and_colour_writes() {
+        let mut r = renderer(4);
+        corner(&mut r, 8.0, 2.0, RED);
+        assert_eq!(r.stats.fragments_tested, 16);
+        assert_eq!(r.stats.fragments_passed_depth, 16);
+        assert_eq!(r.stats.fragments_written, 16);
+
+        // Behind what is already there.
+        corner(&mut r, 8.0, 3.0, BLUE);
+        assert_eq!(r.stats.fragments_tested, 32);
+        assert_eq!(r.stats.fragments_passed_depth, 16);
+        assert_eq!(r.stats.fragments_written, 16);
+
+        // Colour is still written with depth writes off.
+        r.depth_state.write = false;
+        corner(&mut r, 8.0, 1.0, BLUE);
+        assert_eq!(r.stats.fragments_written, 32);
+        assert_eq!(pixels_of(&r, BLUE).len(), 16);
+        assert!(r.depth.iter().all(|&d| d == 2.0));
+
+        r.begin_frame();
+        assert_eq!(r.stats, RenderStats::default());
+    }
+
+    #[test]
+    fn instances_share_their_mesh_and_can_override_its_materials() {
+        const GREEN: Colour = Colour::new(0, 255, 0, 255);
+        let coloured = |diffuse| Material {
+            diffuse,
+            ..Material::default()
+        };
+
+        // A square with a red and a green half.
+        let mut square = mesh(
+            vec![
+                at(-0.5, -0.5, 0.0),
+                at(-0.5, 0.5, 0.0),
+                at(0.5, -0.5, 0.0),
+                at(0.5, 0.5, 0.0),
+            ],
+            &[(0, 1, 2), (1, 3, 2)],
+            coloured(RED),
+        );
+        square.triangles[1].material_index = 1;
+        square.materials.push(coloured(GREEN));
+
+        let mut scene = Scene::new();
+        let id = scene.add_mesh(square);
+        let placed = |x| Transform {
+            translation: Vec3::new(x, 0.0, 3.0),
+            ..Transform::default()
+        };
+        scene.add("plain", placed(-1.0), NodeContent::Mesh(Instance::new(id)));
+        scene.add(
+            "blue",
+            placed(1.0),
+            NodeContent::Mesh(Instance::new(id).with_material(coloured(BLUE))),
+        );
+
+        let camera = Camera::new(32, 32);
+        let draw = |scene: &Scene| {
+            let mut r = renderer(32);
+            r.draw_scene(scene, &camera);
+            let colours_in = |columns: std::ops::Range<usize>| {
+                let mut colours: Vec<_> = (0..32)
+                    .flat_map(|y| columns.clone().map(move |x| (x, y)))
+                    .map(|(x, y)| pixel(&r, x, y))
+                    .filter(|&c| c != BACKGROUND)
+                    .map(|c| (c.red, c.green, c.blue))
+                    .collect();
+                colours.sort();
+                colours.dedup();
+                colours
+            };
+            (colours_in(0..16), colours_in(16..32))
+        };
+
+        let (left, right) = draw(&scene);
+        assert_eq!(left, [(0, 255, 0), (255, 0, 0)]);
+        assert_eq!(right, [(0, 0, 255)]);
+
+        // Editing the mesh's materials only shows where they are not
+        // overridden.
+        scene.mesh_mut(id).materials[0].diffuse = Colour::new(255, 255, 255, 255);
+        let (left, right) = draw(&scene);
+        assert_eq!(left, [(0, 255, 0), (255, 255, 255)]);
+        assert_eq!(right, [(0, 0, 255)]);
+
+        // Both instances draw the one mesh, so moving its vertices moves
+        // both off screen.
+        for v in &mut scene.mesh_mut(id).vertices {
+            v.y -= 10.0;
+        }
+        let (left, right) = draw(&scene);
+        assert!(left.is_empty() && right.is_empty(), "{left:?} {right:?}");
+    }
+
+    #[test]
+    fn line_covers_every_pixel_between_its_ends() {
+        let mut r = renderer(16);
+        r.rasterize_line(&at(2.5, 5.5, 2.0), &at(12.5, 5.5, 2.0), RED);
+
+        let expected: Vec<_> = (2..=12).map(|x| (x, 5)).collect();
+        assert_eq!(pixels_of(&r, RED), expected);
+    }
+
+    #[test]
+    fn line_is_trimmed_to_the_screen() {
+        let mut r = renderer(16);
+        r.rasterize_line(&at(-20.5, 3.5, 2.0), &at(30.5, 3.5, 2.0), RED);
+        r.rasterize_line(&at(-20.5, -5.5, 2.0), &at(30.5, -5.5, 2.0), RED);
+
+        let expected: Vec<_> = (0..16).map(|x| (x, 3)).collect();
+        assert_eq!(pixels_of(&r, RED), expected);
+    }
+
+    #[test]
+    fn line_depth_is_interpolated_as_inverse_z() {
+        let mut r = renderer(16);
+        r.rasterize_line(&at(0.5, 0.5, 1.0), &at(10.5, 0.5, 4.0), RED);
+
+        // Halfway in screen space is halfway between 1/1 and 1/4.
+        let expected = 1.0 / 0.625 * (1.0 - LINE_DEPTH_OFFSET);
+        assert!((r.depth[5] - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn line_is_depth_tested() {
+        let mut r = renderer(16);
+        r.put_pixel_depth(5, 5, 1.0, BLUE);
+        r.rasterize_line(&at(2.5, 5.5, 2.0), &at(8.5, 5.5, 2.0), RED);
+
+        assert_eq!(pixel(&r, 5, 5), BLUE);
+        assert_eq!(pixels_of(&r, RED).len(), 6);
+    }
+
+    #[test]
+    fn wireframe_outlines_the_clipped_polygon() {
+        let corners = [at(0.0, 1.5, -1.0), at(1.5, -1.0, 3.0), at(-1.5, -1.0, 3.0)];
+        let mesh = mesh(corners.to_vec(), &[(0, 1, 2)], Material::default());
+        let camera = Camera::new(64, 64);
+
+        let mut r = renderer(64);
+        r.polygon_mode = PolygonMode::Wireframe;
+        r.cull_mode = CullMode::None;
+        r.draw_mesh(&mesh, &Mat4::IDENTITY, &camera);
+
+        // The near plane cuts off one corner, leaving a quad. Its fan
+        // diagonal must not be drawn.
+        let (clipped, count) = clip_triangle_depth(corners, camera.near, camera.far);
+        assert_eq!(count, 4);
+        let projection = camera.projection_matrix();
+        let screen = clipped.map(|v| camera.view_to_screen(&projection, &v));
+
+        let lit = pixels_of(&r, r.wireframe_colour);
+        assert!(!lit.is_empty());
+        for (x, y) in lit {
+            let p = (x as f64 + 0.5, y as f64 + 0.5);
+            let distance = (0..count)
+                .map(|i| distance_to_segment(p, &screen[i], &screen[(i + 1) % count]))
+                .fold(f64::INFINITY, f64::min);
+            assert!(distance < 1.5, "({x}, {y}) is off the outline");
+        }
+    }
+
+    fn distance_to_segment(p: (f64, f64), a: &Vertex, b: &Vertex) -> f64 {
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let t = (((p.0 - a.x) * dx + (p.1 - a.y) * dy) / (dx * dx + dy * dy)).clamp(0.0, 1.0);
+        (p.0 - a.x - dx * t).hypot(p.1 - a.y - dy * t)
+    }
+
+    #[test]
+    fn close_surfaces_far_away_resolve_in_both_depth_modes() {
+        // The f64 buffer keeps a millimetre apart at 900 units in either
+        // mode; reversed-Z is not what prevents z-fighting here.
+        for mode in [DepthMode::Linear, DepthMode::ReversedZ] {
+            let mut r = renderer(4);
+            r.depth_state = DepthState::with_mode(mode);
+
+            for (first, second) in [
+                ((900.0, BLUE), (900.001, RED)),
+                ((900.001, RED), (900.0, BLUE)),
+            ] {
+                r.clear(BACKGROUND);
+                r.put_pixel_depth(1, 1, first.0, first.1);
+                r.put_pixel_depth(1, 1, second.0, second.1);
+
+                assert_eq!(pixel(&r, 1, 1), BLUE, "{mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn only_reversed_z_draws_beyond_the_far_plane() {
+        let distant = mesh(
+            vec![
+                at(-4000.0, -4000.0, 2000.0),
+                at(4000.0, -4000.0, 2000.0),
+                at(0.0, 4000.0, 2000.0),
+            ],
+            &[(0, 1, 2)],
+            Material {
+                diffuse: RED,
+                double_sided: true,
+                ..Material::default()
+            },
+        );
+        let camera = Camera::new(8, 8);
+        assert!(camera.far < 2000.0);
+
+        for (mode, drawn) in [(DepthMode::Linear, false), (DepthMode::ReversedZ, true)] {
+            let mut r = renderer(8);
+            r.depth_state = DepthState::with_mode(mode);
+            r.clear(BACKGROUND);
+            r.draw_mesh(&distant, &Mat4::IDENTITY, &camera);
+
+            assert_eq!(!pixels_of(&r, RED).is_empty(), drawn, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn resolve_hdr_applies_exposure_then_the_tone_mapper() {
+        let mut r = renderer(2);
+        r.set_hdr(true);
+        r.exposure = 2.0;
+        r.tone_mapper = ToneMapper::Clamp;
+        let hdr = r.hdr.as_mut().unwrap();
+        hdr[0] = [0.25, 0.5, 3.0, 0.5];
+        hdr[1] = [0.0, 0.0, 0.0, 1.0];
+
+        r.resolve_hdr();
+
+        assert_eq!(pixel(&r, 0, 0), Colour::from_linear([0.5, 1.0, 1.0, 0.5]));
+        assert_eq!(pixel(&r, 1, 0), Colour::new(0, 0, 0, 255));
+
+        r.exposure = 1.0;
+        r.tone_mapper = ToneMapper::Reinhard;
+        r.hdr.as_mut().unwrap()[0] = [1.0, 3.0, 0.0, 1.0];
+        r.resolve_hdr();
+
+        assert_eq!(pixel(&r, 0, 0), Colour::from_linear([0.5, 0.75, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn emissive_light_goes_above_one_in_hdr() {
+        let mut r = renderer(2);
+        r.set_hdr(true);
+        r.put_fragment(0, 0, 1.0, RED, Vec3::new(2.0, 0.0, 0.0));
+
+        assert_eq!(r.hdr.as_ref().unwrap()[0], [3.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn hdr_shading_keeps_texels_unquantized() {
+        let texel = Colour::new(1, 1, 1, 255);
+        let material = Material {
+            kd_texture: Some(Texture {
+                width: 1,
+                height: 1,
+                data: vec![texel],
+                path: None,
+                colour_space: ColourSpace::Linear,
+                filter: Filter::Bilinear,
+            }),
+            ..Material::default()
+        };
+
+        let mut r = renderer(4);
+        r.set_hdr(true);
+        r.rasterize_triangle(
+            &at(0.0, 0.0, 1.0),
+            &at(4.0, 0.0, 1.0),
+            &at(0.0, 4.0, 1.0),
+            &material,
+        );
+
+        // Through an 8-bit sRGB texel this would come back as 13 / 255 / 12.92.
+        assert_eq!(
+            r.hdr.as_ref().unwrap()[0],
+            [1.0 / 255.0, 1.0 / 255.0, 1.0 / 255.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn msaa_resolves_partial_coverage_to_a_blend() {
+        let white = Colour::new(255, 255, 255, 255);
+        let material = Material {
+            diffuse: white,
+            ..Material::default()
+        };
+
+        let mut r = renderer(4);
+        r.set_msaa(Msaa::X4);
+        r.clear(BACKGROUND);
+        // The right edge runs down the middle of column 1, leaving two of
+        // its four samples on each side.
+        r.rasterize_triangle(
+            &at(1.5, -10.0, 1.0),
+            &at(1.5, 20.0, 1.0),
+            &at(-20.0, 5.0, 1.0),
+            &material,
+        );
+        r.resolve_msaa();
+
+        assert_eq!(pixel(&r, 0, 2), white);
+        assert_eq!(pixel(&r, 2, 2), BACKGROUND);
+        let edge = pixel(&r, 1, 2);
+        assert!(edge.red > 0 && edge.red < 255, "{edge:?}");
+        // Half the light, blended in linear space.
+        assert_eq!(edge, Colour::new(188, 188, 188, 255));
+    }
+
+    #[test]
+    fn msaa_depth_resolves_to_the_nearest_sample() {
+        for mode in [DepthMode::Linear, DepthMode::ReversedZ] {
+            let mut r = renderer(1);
+            r.depth_state = DepthState::with_mode(mode);
+            r.set_msaa(Msaa::X4);
+            r.clear(BACKGROUND);
+
+            let near = r.depth_near;
+            r.samples.as_mut().unwrap().depth =
+                [5.0, 2.0, 9.0, 3.0].map(|z| mode.encode(z, near)).to_vec();
+            r.resolve_msaa();
+
+            assert_eq!(r.depth[0], mode.encode(2.0, near), "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn msaa_stencil_resolves_to_the_first_sample() {
+        let mut r = renderer(1);
+        r.set_msaa(Msaa::X4);
+        r.samples.as_mut().unwrap().stencil = vec![7, 1, 2, 3];
+        r.resolve_msaa();
+
+        assert_eq!(r.stencil[0], 7);
+    }
+
+    #[test]
+    fn msaa_counts_fragments_per_sample_and_shading_per_pixel() {
+        let mut r = renderer(1);
+        r.set_msaa(Msaa::X4);
+        r.clear(BACKGROUND);
+        r.rasterize_triangle(
+            &at(-5.0, -5.0, 1.0),
+            &at(10.0, -5.0, 1.0),
+            &at(-5.0, 10.0, 1.0),
+            &Material::default(),
+        );
+
+        assert_eq!(r.stats.pixels_shaded, 1);
+        assert_eq!(r.stats.fragments_tested, 4);
+        assert_eq!(r.stats.fragments_passed_depth, 4);
+        assert_eq!(r.stats.fragments_written, 4);
+    }
+
+    /// Fills the pixels with `x + y <= size` at depth `z`, whichever way
+    /// the triangle faces. Each pixel is drawn once, so stencil counts are
+    /// exact.
+    fn corner(r: &mut Renderer, size: f64, z: f64, colour: Colour) {
+        let m = Material {
+            diffuse: colour,
+            double_sided: true,
+            ..Material::default()
+        };
+        r.fill_triangle(&at(0.0, 0.0, z), &at(size, 0.0, z), &at(0.0, size, z), &m);
+    }
+
+    fn stencil_at(r: &Renderer, x: usize, y: usize) -> u8 {
+        r.stencil[y * r.width + x]
+    }
+
+    fn stencil(compare: CompareFunc, reference: u8) -> StencilState {
+        StencilState {
+            enabled: true,
+            compare,
+            reference,
+            ..StencilState::default()
+        }
+    }
+
+    #[test]
+    fn stencil_masks_a_later_draw_to_an_earlier_one() {
+        let mut r = renderer(8);
+
+        // Mark the top-left corner with 1 while drawing it red.
+        r.stencil_state = StencilState {
+            pass: StencilOp::Replace,
+            ..stencil(CompareFunc::Always, 1)
+        };
+        corner(&mut r, 3.0, 2.0, RED);
+
+        // A nearer full-screen draw only lands outside the mark.
+        r.stencil_state = stencil(CompareFunc::NotEqual, 1);
+        corner(&mut r, 16.0, 1.0, BLUE);
+
+        for y in 0..8 {
+            for x in 0..8 {
+                let (colour, mark) = if x + y <= 3 { (RED, 1) } else { (BLUE, 0) };
+                assert_eq!(pixel(&r, x, y), colour, "({x}, {y})");
+                assert_eq!(stencil_at(&r, x, y), mark, "({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn stencil_compare_functions_pick_the_expected_pixels() {
+        // Pixels hold stencil values 0 to 3; the reference is 2.
+        let cases = [
+            (CompareFunc::Never, vec![]),
+            (CompareFunc::Less, vec![3]),
+            (CompareFunc::LessEqual, vec![2, 3]),
+            (CompareFunc::Equal, vec![2]),
+            (CompareFunc::NotEqual, vec![0, 1, 3]),
+            (CompareFunc::GreaterEqual, vec![0, 1, 2]),
+            (CompareFunc::Greater, vec![0, 1]),
+            (CompareFunc::Always, vec![0, 1, 2, 3]),
+        ];
+
+        for (compare, expected) in cases {
+            let mut r = renderer(2);
+            r.stencil.copy_from_slice(&[0, 1, 2, 3]);
+            r.stencil_state = stencil(compare, 2);
+            corner(&mut r, 4.0, 1.0, RED);
+
+            let drawn: Vec<_> = pixels_of(&r, RED)
+                .into_iter()
+                .map(|(x, y)| r.stencil[y * 2 + x])
+                .collect();
+            assert_eq!(drawn, expected, "{compare:?}");
+        }
+    }
+
+    #[test]
+    fn stencil_ops_follow_which_test_failed() {
+        let mut r = renderer(3);
+
+        // Pixel 0 fails the stencil test, pixel 1 the depth test, and
+        // pixel 2 passes both.
+        r.stencil[0] = 5;
+        r.put_pixel_depth(1, 0, 0.5, BLUE);
+        r.stencil_state = StencilState {
+            fail: StencilOp::Zero,
+            depth_fail: StencilOp::IncrementClamp,
+            pass: StencilOp::DecrementWrap,
+            ..stencil(CompareFunc::Equal, 0)
+        };
+        corner(&mut r, 6.0, 1.0, RED);
+
+        assert_eq!(&r.stencil[..3], [0, 1, 255]);
+        assert_eq!([0, 1, 2].map(|x| pixel(&r, x, 0)), [BACKGROUND, BLUE, RED]);
+    }
+
+    #[test]
+    fn overlapping_draws_count_up_and_clamp_or_wrap() {
+        for (op, top) in [
+            (StencilOp::IncrementClamp, 255),
+            (StencilOp::IncrementWrap, 0),
+        ] {
+            let mut r = renderer(4);
+            r.clear_stencil(253);
+            r.stencil_state = StencilState {
+                pass: op,
+                ..stencil(CompareFunc::Always, 0)
+            };
+            r.depth_state.compare = CompareFunc::Always;
+
+            // Nested corners: (0, 0) is under all three, (1, 1) under two.
+            corner(&mut r, 8.0, 1.0, RED);
+            corner(&mut r, 3.0, 1.0, RED);
+            corner(&mut r, 1.0, 1.0, RED);
+
+            assert_eq!(stencil_at(&r, 0, 0), top, "{op:?}");
+            assert_eq!(stencil_at(&r, 1, 1), 255, "{op:?}");
+            assert_eq!(stencil_at(&r, 3, 3), 254, "{op:?}");
+        }
+    }
+
+    #[test]
+    fn stencil_masks_limit_what_is_compared_and_written() {
+        let mut r = renderer(2);
+        r.stencil[0] = 0x12;
+        r.stencil[1] = 0x13;
+        r.stencil_state = StencilState {
+            read_mask: 0x0F,
+            write_mask: 0xF0,
+            pass: StencilOp::Replace,
+            ..stencil(CompareFunc::Equal, 0xA2)
+        };
+        corner(&mut r, 4.0, 1.0, RED);
+
+        // Only the low bits are compared and only the high bits replaced.
+        assert!(pixels_of(&r, RED).contains(&(0, 0)));
+        assert!(!pixels_of(&r, RED).contains(&(1, 0)));
+        assert_eq!(&r.stencil[..2], [0xA2, 0x13]);
+    }
+
+    #[test]
+    fn stencil_rejected_fragments_are_counted_as_tested() {
+        let mut r = renderer(4);
+        r.stencil_state = stencil(CompareFunc::Never, 0);
+        corner(&mut r, 8.0, 1.0, RED);
+
+        assert_eq!(r.stats.fragments_tested, 16);
+        assert_eq!(r.stats.fragments_passed_depth, 0);
+        assert!(pixels_of(&r, RED).is_empty());
+    }
+
+    const WHITE: Colour = Colour::new(255, 255, 255, 255);
+
+    #[test]
+    fn blend_pixel_blends_by_alpha_in_linear_light() {
+        let mut r = renderer(2);
+        r.clear(WHITE);
+
+        r.blend_pixel(0, 0, Colour::new(0, 0, 0, 128));
+        r.blend_pixel(1, 0, Colour::new(0, 0, 0, 0));
+        r.blend_pixel(0, 1, RED);
+
+        // Just under half the light of white (alpha 128 is 0.502), not half
+        // its encoded value.
+        assert_eq!(pixel(&r, 0, 0), Colour::new(187, 187, 187, 255));
+        assert_eq!(pixel(&r, 1, 0), WHITE);
+        assert_eq!(pixel(&r, 0, 1), RED);
+    }
+
+    #[test]
+    fn overlay_drawing_is_clipped_to_the_buffer() {
+        let mut r = renderer(4);
+
+        for (x, y) in [(-1, 0), (0, -1), (4, 0), (0, 4), (i32::MIN, i32::MAX)] {
+            r.blend_pixel(x, y, RED);
+        }
+        assert!(pixels_of(&r, RED).is_empty());
+
+        r.fill_rect(-2, -3, 4, 5, RED);
+        let expected: Vec<_> = (0..2).flat_map(|y| (0..2).map(move |x| (x, y))).collect();
+        assert_eq!(pixels_of(&r, RED), expected);
+
+        r.clear(BACKGROUND);
+        r.fill_rect(3, 2, 10, 10, RED);
+        assert_eq!(pixels_of(&r, RED), [(3, 2), (3, 3)]);
+
+        r.clear(BACKGROUND);
+        r.draw_line_2d(-3, 1, 6, 1, RED);
+        let expected: Vec<_> = (0..4).map(|x| (x, 1)).collect();
+        assert_eq!(pixels_of(&r, RED), expected);
+    }
+
+    #[test]
+    fn line_2d_includes_both_ends() {
+        let mut r = renderer(4);
+        r.draw_line_2d(3, 3, 0, 0, RED);
+
+        assert_eq!(pixels_of(&r, RED), [(0, 0), (1, 1), (2, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn text_is_drawn_from_the_font() {
+        let mut r = renderer(24);
+        r.draw_text(2, 1, "-\n-", RED);
+
+        // '-' is a bar six pixels wide on the fourth row of its cell, and
+        // the second line starts a glyph height further down.
+        let bar = |top: usize| (2..8).map(move |x| (x, top + 3));
+        let expected: Vec<_> = bar(1).chain(bar(1 + GLYPH_HEIGHT)).collect();
+        assert_eq!(pixels_of(&r, RED), expected);
+    }
+
+    #[test]
+    fn text_outside_the_font_is_drawn_as_a_question_mark() {
+        let draw = |text: &str| {
+            let mut r = renderer(10);
+            r.draw_text(1, 1, text, RED);
+            pixels_of(&r, RED)
+        };
+
+        assert!(!draw("?").is_empty());
+        assert_eq!(draw("\u{e9}"), draw("?"));
+        assert_eq!(draw("\t"), draw("?"));
+    }
+
+    #[test]
+    fn text_off_the_edge_is_clipped() {
+        let mut r = renderer(6);
+        r.draw_text(-4, -4, "##", RED);
+        r.draw_text(3, 3, "##", RED);
+
+        assert!(!pixels_of(&r, RED).is_empty());
+    }
+}
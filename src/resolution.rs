@@ -0,0 +1,266 @@
+use std::time::Duration;
+
+use crate::primitives::colour::Colour;
+
+/// Frames to average after a change before the scale is adjusted again.
+const SETTLE_FRAMES: usize = 10;
+
+/// Relative scale change below which dynamic scaling leaves the size alone,
+/// so buffers are not reallocated every frame.
+const HYSTERESIS: f64 = 0.05;
+
+/// Size of the internal render resolution relative to the output, e.g. 0.5
+/// to draw a quarter of the pixels or 2 to supersample.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderScale {
+    pub scale: f64,
+    /// Bounds for dynamic scaling.
+    pub min: f64,
+    pub max: f64,
+    /// Frame time that dynamic scaling aims for. `None` keeps `scale` fixed.
+    pub target: Option<Duration>,
+    average: Option<f64>,
+    frames: usize,
+}
+
+impl Default for RenderScale {
+    fn default() -> Self {
+        Self::fixed(1.0)
+    }
+}
+
+impl RenderScale {
+    pub fn fixed(scale: f64) -> Self {
+        Self {
+            scale,
+            min: 0.25,
+            max: 2.0,
+            target: None,
+            average: None,
+            frames: 0,
+        }
+    }
+
+    /// Starts at full resolution and adjusts towards `target` frame time.
+    pub fn dynamic(target: Duration) -> Self {
+        Self {
+            target: Some(target),
+            ..Self::fixed(1.0)
+        }
+    }
+
+    /// The render size for an output of `width` by `height`, at least one
+    /// pixel each way.
+    pub fn size(&self, width: usize, height: usize) -> (usize, usize) {
+        let scaled = |n: usize| ((n as f64 * self.scale).round() as usize).max(1);
+        (scaled(width), scaled(height))
+    }
+
+    /// Feeds in the time of a finished frame and, with a target set, moves
+    /// `scale` towards the one expected to hit it. Cost is assumed to grow
+    /// with the pixel count. Returns whether `scale` changed.
+    pub fn update(&mut self, frame_time: Duration) -> bool {
+        let Some(target) = self.target else {
+            return false;
+        };
+
+        let secs = frame_time.as_secs_f64();
+        let average = match self.average {
+            Some(a) => a + (secs - a) * 0.1,
+            None => secs,
+        };
+        self.average = Some(average);
+        self.frames += 1;
+
+        if self.frames < SETTLE_FRAMES || average <= 0.0 {
+            return false;
+        }
+
+        let ideal =
+            (self.scale * (target.as_secs_f64() / average).sqrt()).clamp(self.min, self.max);
+        if (ideal - self.scale).abs() < self.scale * HYSTERESIS {
+            return false;
+        }
+
+        self.scale = ideal;
+        self.average = None;
+        self.frames = 0;
+        true
+    }
+}
+
+/// Scales an RGBA image to a new size in linear light: averaging the
+/// covered pixels when shrinking, bilinear when enlarging. Same-size images
+/// are copied unchanged.
+pub fn resample(src: &[u8], src_size: (usize, usize), dst: &mut [u8], dst_size: (usize, usize)) {
+    if src_size == dst_size {
+        dst.copy_from_slice(src);
+        return;
+    }
+
+    let linear: Vec<[f32; 4]> = src
+        .chunks_exact(4)
+        .map(|px| Colour::new(px[0], px[1], px[2], px[3]).to_linear())
+        .collect();
+
+    let columns = weights(src_size.0, dst_size.0);
+    let rows = weights(src_size.1, dst_size.1);
+
+    for (y, row) in rows.iter().enumerate() {
+        for (x, column) in columns.iter().enumerate() {
+            let mut sum = [0.0; 4];
+            for &(sy, wy) in row {
+                for &(sx, wx) in column {
+                    let px = linear[sy * src_size.0 + sx];
+                    for c in 0..4 {
+                        sum[c] += px[c] * wx * wy;
+                    }
+                }
+            }
+
+            let colour = Colour::from_linear(sum);
+            let i = (y * dst_size.0 + x) * 4;
+            dst[i..i + 4].copy_from_slice(&[colour.red, colour.green, colour.blue, colour.alpha]);
+        }
+    }
+}
+
+/// For each output pixel along one axis, the input pixels it reads and
+/// their weights, which sum to 1.
+fn weights(src_len: usize, dst_len: usize) -> Vec<Vec<(usize, f32)>> {
+    let ratio = src_len as f64 / dst_len as f64;
+
+    (0..dst_len)
+        .map(|d| {
+            if ratio >= 1.0 {
+                let (start, end) = (d as f64 * ratio, (d + 1) as f64 * ratio);
+                (start.floor() as usize..(end.ceil() as usize).min(src_len))
+                    .map(|s| {
+                        let overlap = end.min(s as f64 + 1.0) - start.max(s as f64);
+                        (s, (overlap / ratio) as f32)
+                    })
+                    .collect()
+            } else {
+                let centre = ((d as f64 + 0.5) * ratio - 0.5).clamp(0.0, (src_len - 1) as f64);
+                let s = centre as usize;
+                let t = (centre - s as f64) as f32;
+                vec![(s, 1.0 - t), ((s + 1).min(src_len - 1), t)]
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET: Duration = Duration::from_millis(10);
+
+    /// Runs `frames` frames whose cost grows with the pixel count, taking
+    /// `full` at scale 1.
+    fn simulate(scale: &mut RenderScale, full: Duration, frames: usize) {
+        for _ in 0..frames {
+            let time = full.mul_f64(scale.scale * scale.scale);
+            scale.update(time);
+        }
+    }
+
+    #[test]
+    fn weights_sum_to_one() {
+        for (src, dst) in [(7, 3), (10, 4), (9, 9), (3, 8), (5, 12), (1, 5), (5, 1)] {
+            for (d, taps) in weights(src, dst).iter().enumerate() {
+                let sum: f32 = taps.iter().map(|&(_, w)| w).sum();
+                assert!((sum - 1.0).abs() < 1e-5, "{src} -> {dst}, pixel {d}: {sum}");
+                assert!(taps.iter().all(|&(s, w)| s < src && w >= 0.0));
+            }
+        }
+    }
+
+    #[test]
+    fn same_size_is_an_exact_copy() {
+        let src: Vec<u8> = (0..5 * 3 * 4).map(|i| (i * 37 % 256) as u8).collect();
+        let mut dst = vec![0; src.len()];
+
+        resample(&src, (5, 3), &mut dst, (5, 3));
+        assert_eq!(dst, src);
+    }
+
+    #[test]
+    fn flat_colour_stays_flat_both_ways() {
+        let colour = [200, 30, 90, 255];
+        let src = colour.repeat(6 * 4);
+
+        for size in [(3, 2), (13, 9)] {
+            let mut dst = vec![0; size.0 * size.1 * 4];
+            resample(&src, (6, 4), &mut dst, size);
+            assert!(dst.chunks_exact(4).all(|px| px == colour), "{size:?}");
+        }
+    }
+
+    #[test]
+    fn shrinking_averages_in_linear_light() {
+        let src = [[0, 0, 0, 255], [255, 255, 255, 255]].concat();
+        let mut dst = vec![0; 4];
+
+        resample(&src, (2, 1), &mut dst, (1, 1));
+        assert_eq!(dst, [188, 188, 188, 255]);
+    }
+
+    #[test]
+    fn size_rounds_and_never_reaches_zero() {
+        assert_eq!(RenderScale::fixed(0.5).size(801, 600), (401, 300));
+        assert_eq!(RenderScale::fixed(0.01).size(10, 10), (1, 1));
+    }
+
+    #[test]
+    fn fixed_scale_ignores_frame_times() {
+        let mut scale = RenderScale::fixed(0.75);
+        simulate(&mut scale, Duration::from_secs(1), 100);
+
+        assert_eq!(scale.scale, 0.75);
+    }
+
+    #[test]
+    fn dynamic_scale_converges_on_the_target() {
+        let mut scale = RenderScale::dynamic(TARGET);
+        // Four times too slow at full resolution: half the size is right.
+        simulate(&mut scale, TARGET * 4, 200);
+
+        assert!(
+            (scale.scale - 0.5).abs() < 0.5 * HYSTERESIS,
+            "{}",
+            scale.scale
+        );
+    }
+
+    #[test]
+    fn dynamic_scale_waits_for_frames_to_settle() {
+        let mut scale = RenderScale::dynamic(TARGET);
+
+        for _ in 1..SETTLE_FRAMES {
+            assert!(!scale.update(TARGET * 4));
+        }
+        assert!(scale.update(TARGET * 4));
+    }
+
+    #[test]
+    fn dynamic_scale_stays_within_its_bounds() {
+        let mut scale = RenderScale::dynamic(TARGET);
+        simulate(&mut scale, TARGET * 1000, 200);
+        assert_eq!(scale.scale, scale.min);
+
+        let mut scale = RenderScale::dynamic(TARGET);
+        simulate(&mut scale, TARGET / 1000, 200);
+        assert_eq!(scale.scale, scale.max);
+    }
+
+    #[test]
+    fn small_misses_leave_the_scale_alone() {
+        let mut scale = RenderScale::dynamic(TARGET);
+
+        for _ in 0..100 {
+            assert!(!scale.update(TARGET.mul_f64(1.05)));
+        }
+        assert_eq!(scale.scale, 1.0);
+    }
+}
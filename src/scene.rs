@@ -0,0 +1,332 @@
+use crate::{
+    camera::Camera,
+    light::Light,
+    mesh::Mesh,
+    primitives::{material::Material, matrix::Mat4},
+    transform::Transform,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MeshId(usize);
+
+/// A placement of shared mesh geometry. Many instances can draw the same
+/// mesh; only the node transform and optional material differ.
+#[derive(Debug, Clone)]
+pub struct Instance {
+    pub mesh: MeshId,
+    /// Replaces every material of the mesh for this instance. Boxed so
+    /// that nodes without one stay small.
+    pub material: Option<Box<Material>>,
+}
+
+impl Instance {
+    pub fn new(mesh: MeshId) -> Self {
+        Self {
+            mesh,
+            material: None,
+        }
+    }
+
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = Some(Box::new(material));
+        self
+    }
+}
+
+#[derive(Debug)]
+pub enum NodeContent {
+    Empty,
+    /// The mesh's own transform is applied first, inside the node's space.
+    Mesh(Instance),
+    Camera(Camera),
+    Light(Light),
+}
+
+#[derive(Debug)]
+pub struct Node {
+    pub name: String,
+    pub transform: Transform,
+    pub content: NodeContent,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+impl Node {
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+/// A hierarchy of nodes, each placed relative to its parent, plus the mesh
+/// geometry their instances share.
+#[derive(Debug, Default)]
+pub struct Scene {
+    nodes: Vec<Node>,
+    meshes: Vec<Mesh>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a node at the top level of the scene.
+    pub fn add(&mut self, name: &str, transform: Transform, content: NodeContent) -> NodeId {
+        self.insert(None, name, transform, content)
+    }
+
+    /// Adds a node that inherits `parent`'s world transform.
+    pub fn add_child(
+        &mut self,
+        parent: NodeId,
+        name: &str,
+        transform: Transform,
+        content: NodeContent,
+    ) -> NodeId {
+        self.insert(Some(parent), name, transform, content)
+    }
+
+    fn insert(
+        &mut self,
+        parent: Option<NodeId>,
+        name: &str,
+        transform: Transform,
+        content: NodeContent,
+    ) -> NodeId {
+        let id = NodeId(self.nodes.len());
+
+        self.nodes.push(Node {
+            name: name.to_string(),
+            transform,
+            content,
+            parent,
+            children: Vec::new(),
+        });
+
+        if let Some(parent) = parent {
+            self.nodes[parent.0].children.push(id);
+        }
+
+        id
+    }
+
+    /// Moves `id` under `parent`, or to the top level with `None`. The local
+    /// transform is kept, so the node moves with its new parent.
+    ///
+    /// # Panics
+    ///
+    /// If `parent` is `id` itself or one of its descendants.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        let mut ancestor = parent;
+        while let Some(a) = ancestor {
+            assert!(a != id, "node {id:?} cannot be its own ancestor");
+            ancestor = self.node(a).parent;
+        }
+
+        if let Some(old) = self.nodes[id.0].parent {
+            self.nodes[old.0].children.retain(|&child| child != id);
+        }
+        if let Some(new) = parent {
+            self.nodes[new.0].children.push(id);
+        }
+        self.nodes[id.0].parent = parent;
+    }
+
+    /// Stores mesh geometry for instances to reference. The mesh is not drawn
+    /// until a node holds an [`Instance`] of it.
+    pub fn add_mesh(&mut self, mesh: Mesh) -> MeshId {
+        self.meshes.push(mesh);
+        MeshId(self.meshes.len() - 1)
+    }
+
+    pub fn mesh(&self, id: MeshId) -> &Mesh {
+        &self.meshes[id.0]
+    }
+
+    pub fn mesh_mut(&mut self, id: MeshId) -> &mut Mesh {
+        &mut self.meshes[id.0]
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id.0]
+    }
+
+    pub fn roots(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.ids().filter(|&id| self.node(id).parent.is_none())
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = NodeId> + use<> {
+        (0..self.nodes.len()).map(NodeId)
+    }
+
+    /// Composes the local transforms from the root down to `id`.
+    pub fn world_transform(&self, id: NodeId) -> Mat4 {
+        let node = self.node(id);
+        let local = node.transform.model_matrix();
+
+        match node.parent {
+            Some(parent) => self.world_transform(parent) * local,
+            None => local,
+        }
+    }
+
+    /// Visits every node depth-first, parents before children, with its
+    /// world transform.
+    pub fn traverse(&self, mut visit: impl FnMut(NodeId, &Node, &Mat4)) {
+        let mut stack: Vec<(NodeId, Mat4)> = self.roots().map(|id| (id, Mat4::IDENTITY)).collect();
+        stack.reverse();
+
+        while let Some((id, parent_world)) = stack.pop() {
+            let node = self.node(id);
+            let world = parent_world * node.transform.model_matrix();
+
+            visit(id, node, &world);
+
+            for &child in node.children.iter().rev() {
+                stack.push((child, world));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::{colour::Colour, vector::Vec3};
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-9, "{a:?} != {b:?}");
+    }
+
+    fn moved(x: f64, y: f64, z: f64) -> Transform {
+        Transform {
+            translation: Vec3::new(x, y, z),
+            ..Transform::default()
+        }
+    }
+
+    fn origin(scene: &Scene, id: NodeId) -> Vec3 {
+        scene.world_transform(id).transform_point(Vec3::default())
+    }
+
+    #[test]
+    fn world_transform_is_parent_times_child() {
+        let mut scene = Scene::new();
+        let parent = scene.add(
+            "parent",
+            Transform {
+                scale: Vec3::ONE * 2.0,
+                ..moved(10.0, 0.0, 0.0)
+            },
+            NodeContent::Empty,
+        );
+        let child = scene.add_child(parent, "child", moved(1.0, 2.0, 3.0), NodeContent::Empty);
+        let grandchild = scene.add_child(
+            child,
+            "grandchild",
+            moved(0.0, 0.0, 1.0),
+            NodeContent::Empty,
+        );
+
+        assert_eq!(
+            scene.world_transform(child),
+            scene.node(parent).transform.model_matrix()
+                * scene.node(child).transform.model_matrix()
+        );
+        assert_close(origin(&scene, child), Vec3::new(12.0, 4.0, 6.0));
+        assert_close(origin(&scene, grandchild), Vec3::new(12.0, 4.0, 8.0));
+    }
+
+    #[test]
+    fn traverse_visits_parents_first_with_world_transforms() {
+        let mut scene = Scene::new();
+        let a = scene.add("a", moved(1.0, 0.0, 0.0), NodeContent::Empty);
+        let b = scene.add_child(a, "b", moved(0.0, 1.0, 0.0), NodeContent::Empty);
+        let c = scene.add("c", moved(0.0, 0.0, 1.0), NodeContent::Empty);
+        let d = scene.add_child(b, "d", moved(1.0, 1.0, 1.0), NodeContent::Empty);
+
+        let mut visited = Vec::new();
+        scene.traverse(|id, _, world| {
+            assert_eq!(*world, scene.world_transform(id));
+            visited.push(id);
+        });
+
+        assert_eq!(visited, [a, b, d, c]);
+    }
+
+    #[test]
+    fn reparenting_keeps_the_local_transform() {
+        let mut scene = Scene::new();
+        let left = scene.add("left", moved(-5.0, 0.0, 0.0), NodeContent::Empty);
+        let right = scene.add("right", moved(5.0, 0.0, 0.0), NodeContent::Empty);
+        let child = scene.add_child(left, "child", moved(0.0, 1.0, 0.0), NodeContent::Empty);
+
+        scene.set_parent(child, Some(right));
+        assert_eq!(scene.node(child).parent(), Some(right));
+        assert!(scene.node(left).children().is_empty());
+        assert_eq!(scene.node(right).children(), [child]);
+        assert_close(origin(&scene, child), Vec3::new(5.0, 1.0, 0.0));
+
+        scene.set_parent(child, None);
+        assert!(scene.node(right).children().is_empty());
+        assert_eq!(scene.roots().collect::<Vec<_>>(), [left, right, child]);
+        assert_close(origin(&scene, child), Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "own ancestor")]
+    fn reparenting_under_a_descendant_panics() {
+        let mut scene = Scene::new();
+        let a = scene.add("a", Transform::default(), NodeContent::Empty);
+        let b = scene.add_child(a, "b", Transform::default(), NodeContent::Empty);
+
+        scene.set_parent(a, Some(b));
+    }
+
+    #[test]
+    fn instances_share_one_mesh_and_keep_their_own_material() {
+        let mut scene = Scene::new();
+        let id = scene.add_mesh(Mesh {
+            vertices: Vec::new(),
+            triangles: Vec::new(),
+            transform: Transform::default(),
+            materials: vec![Material::default()],
+            sources: Vec::new(),
+        });
+        let blue = Material {
+            diffuse: Colour::new(0, 0, 255, 255),
+            ..Material::default()
+        };
+
+        let plain = scene.add(
+            "plain",
+            moved(-1.0, 0.0, 0.0),
+            NodeContent::Mesh(Instance::new(id)),
+        );
+        let tinted = scene.add(
+            "tinted",
+            moved(1.0, 0.0, 0.0),
+            NodeContent::Mesh(Instance::new(id).with_material(blue.clone())),
+        );
+
+        let instance = |node| match &scene.node(node).content {
+            NodeContent::Mesh(instance) => instance.clone(),
+            _ => unreachable!(),
+        };
+        assert_eq!(instance(plain).mesh, id);
+        assert_eq!(instance(tinted).mesh, id);
+        assert_eq!(instance(plain).material, None);
+        assert_eq!(instance(tinted).material, Some(Box::new(blue)));
+    }
+}
@@ -0,0 +1,361 @@
+//! Fixed-function pipeline settings applied by
+//! [`crate::renderer::Renderer`] to each draw.
+
+use crate::primitives::vertex::Vertex;
+
+/// Which triangles face culling throws away.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CullMode {
+    #[default]
+    Back,
+    Front,
+    /// Draws both sides, e.g. for leaves and other thin geometry.
+    None,
+}
+
+impl CullMode {
+    /// Whether a triangle with this facing is dropped.
+    pub fn culls(self, back_facing: bool) -> bool {
+        match self {
+            Self::Back => back_facing,
+            Self::Front => !back_facing,
+            Self::None => false,
+        }
+    }
+
+    /// The mode after this one, wrapping around, for cycling with a key.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Back => Self::Front,
+            Self::Front => Self::None,
+            Self::None => Self::Back,
+        }
+    }
+}
+
+/// The on-screen winding of front-facing triangles.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FrontFace {
+    #[default]
+    Clockwise,
+    CounterClockwise,
+}
+
+impl FrontFace {
+    pub fn flipped(self) -> Self {
+        match self {
+            Self::Clockwise => Self::CounterClockwise,
+            Self::CounterClockwise => Self::Clockwise,
+        }
+    }
+
+    /// Whether a screen-space triangle faces away from the viewer.
+    /// Degenerate triangles count as back-facing for either winding.
+    pub fn is_back_facing(self, v0: &Vertex, v1: &Vertex, v2: &Vertex) -> bool {
+        let winding = (v1.x - v0.x) * (v2.y - v0.y) - (v1.y - v0.y) * (v2.x - v0.x);
+
+        match self {
+            Self::Clockwise => winding <= 0.0,
+            Self::CounterClockwise => winding >= 0.0,
+        }
+    }
+}
+
+/// How an incoming value is compared with a stored one, for depth and
+/// stencil tests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompareFunc {
+    Never,
+    #[default]
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl CompareFunc {
+    /// Whether `value` passes against `stored`, e.g. `value < stored` for
+    /// [`CompareFunc::Less`].
+    pub fn passes<T: PartialOrd>(self, value: T, stored: T) -> bool {
+        match self {
+            Self::Never => false,
+            Self::Less => value < stored,
+            Self::Equal => value == stored,
+            Self::LessEqual => value <= stored,
+            Self::Greater => value > stored,
+            Self::NotEqual => value != stored,
+            Self::GreaterEqual => value >= stored,
+            Self::Always => true,
+        }
+    }
+}
+
+/// What the depth buffer stores for a fragment at view-space depth `z`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DepthMode {
+    /// `z` itself. Nearer is smaller.
+    #[default]
+    Linear,
+    /// `near / z`: 1 at the near plane falling to 0 at infinity, so there
+    /// is no far plane. Nearer is greater.
+    ///
+    /// The buffer is `f64` in either mode, and view-space `z` already keeps
+    /// its relative precision at any distance, so this does not separate
+    /// close surfaces any better than [`DepthMode::Linear`]. What it adds is
+    /// drawing beyond the camera's far plane.
+    ReversedZ,
+}
+
+impl DepthMode {
+    /// Stored depth for view-space depth `z`, given the camera's near plane.
+    pub fn encode(self, z: f64, near: f64) -> f64 {
+        match self {
+            Self::Linear => z,
+            Self::ReversedZ => near / z,
+        }
+    }
+
+    /// View-space depth for a stored value. Cleared pixels decode to
+    /// infinity.
+    pub fn decode(self, depth: f64, near: f64) -> f64 {
+        match self {
+            Self::Linear => depth,
+            Self::ReversedZ => near / depth,
+        }
+    }
+
+    /// The value of an empty depth buffer, farther than anything drawn.
+    pub fn clear_value(self) -> f64 {
+        match self {
+            Self::Linear => f64::INFINITY,
+            Self::ReversedZ => 0.0,
+        }
+    }
+
+    /// The compare function that keeps the nearest fragment.
+    pub fn nearer(self) -> CompareFunc {
+        match self {
+            Self::Linear => CompareFunc::Less,
+            Self::ReversedZ => CompareFunc::Greater,
+        }
+    }
+
+    /// The mode after this one, wrapping around, for cycling with a key.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Linear => Self::ReversedZ,
+            Self::ReversedZ => Self::Linear,
+        }
+    }
+}
+
+/// Depth test and write settings. `compare` works on stored values, so a
+/// reversed [`DepthMode`] needs [`CompareFunc::Greater`] to keep the
+/// nearest fragment; [`DepthState::with_mode`] picks it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthState {
+    pub compare: CompareFunc,
+    /// Whether passing fragments update the depth buffer.
+    pub write: bool,
+    /// Added to a fragment's view-space depth before encoding, in world
+    /// units. Positive values push it away from the camera.
+    pub bias: f64,
+    pub mode: DepthMode,
+}
+
+impl Default for DepthState {
+    fn default() -> Self {
+        Self::with_mode(DepthMode::default())
+    }
+}
+
+impl DepthState {
+    /// Testing and writing enabled, keeping the nearest fragment.
+    pub fn with_mode(mode: DepthMode) -> Self {
+        Self {
+            compare: mode.nearer(),
+            write: true,
+            bias: 0.0,
+            mode,
+        }
+    }
+}
+
+/// What happens to a stencil value after a test.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StencilOp {
+    #[default]
+    Keep,
+    Zero,
+    /// Store the reference value.
+    Replace,
+    /// Add one, stopping at 255.
+    IncrementClamp,
+    /// Subtract one, stopping at 0.
+    DecrementClamp,
+    Invert,
+    /// Add one, wrapping 255 to 0.
+    IncrementWrap,
+    /// Subtract one, wrapping 0 to 255.
+    DecrementWrap,
+}
+
+impl StencilOp {
+    /// The new value for `stored`, before the write mask is applied.
+    pub fn apply(self, stored: u8, reference: u8) -> u8 {
+        match self {
+            Self::Keep => stored,
+            Self::Zero => 0,
+            Self::Replace => reference,
+            Self::IncrementClamp => stored.saturating_add(1),
+            Self::DecrementClamp => stored.saturating_sub(1),
+            Self::Invert => !stored,
+            Self::IncrementWrap => stored.wrapping_add(1),
+            Self::DecrementWrap => stored.wrapping_sub(1),
+        }
+    }
+}
+
+/// Stencil test and update settings. A fragment passes when
+/// `compare.passes(reference & read_mask, stored & read_mask)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StencilState {
+    /// When off, the stencil buffer is neither tested nor written.
+    pub enabled: bool,
+    pub compare: CompareFunc,
+    pub reference: u8,
+    pub read_mask: u8,
+    /// Bits of the stencil value that the operations may change.
+    pub write_mask: u8,
+    /// Applied when the stencil test fails.
+    pub fail: StencilOp,
+    /// Applied when the stencil test passes but the depth test fails.
+    pub depth_fail: StencilOp,
+    /// Applied when both tests pass.
+    pub pass: StencilOp,
+}
+
+impl Default for StencilState {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            compare: CompareFunc::Always,
+            reference: 0,
+            read_mask: 0xFF,
+            write_mask: 0xFF,
+            fail: StencilOp::Keep,
+            depth_fail: StencilOp::Keep,
+            pass: StencilOp::Keep,
+        }
+    }
+}
+
+impl StencilState {
+    pub fn test(&self, stored: u8) -> bool {
+        self.compare
+            .passes(self.reference & self.read_mask, stored & self.read_mask)
+    }
+
+    /// `stored` after `op`, keeping the bits outside `write_mask`.
+    pub fn update(&self, stored: u8, op: StencilOp) -> u8 {
+        let value = op.apply(stored, self.reference);
+        (stored & !self.write_mask) | (value & self.write_mask)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: f64, y: f64) -> Vertex {
+        Vertex::new(x, y, 1.0, 0.0, 0.0)
+    }
+
+    /// Clockwise on screen, where y points down.
+    const CLOCKWISE: [(f64, f64); 3] = [(0.0, 0.0), (4.0, 0.0), (0.0, 4.0)];
+    const COUNTER_CLOCKWISE: [(f64, f64); 3] = [(0.0, 0.0), (0.0, 4.0), (4.0, 0.0)];
+
+    fn back_facing(front_face: FrontFace, corners: [(f64, f64); 3]) -> bool {
+        let [a, b, c] = corners.map(|(x, y)| at(x, y));
+        front_face.is_back_facing(&a, &b, &c)
+    }
+
+    #[test]
+    fn front_face_decides_which_winding_is_back_facing() {
+        assert!(!back_facing(FrontFace::Clockwise, CLOCKWISE));
+        assert!(back_facing(FrontFace::Clockwise, COUNTER_CLOCKWISE));
+        assert!(back_facing(FrontFace::CounterClockwise, CLOCKWISE));
+        assert!(!back_facing(FrontFace::CounterClockwise, COUNTER_CLOCKWISE));
+
+        assert_eq!(FrontFace::Clockwise.flipped(), FrontFace::CounterClockwise);
+        assert_eq!(FrontFace::CounterClockwise.flipped(), FrontFace::Clockwise);
+    }
+
+    #[test]
+    fn degenerate_triangles_are_back_facing_either_way() {
+        let line = [(0.0, 0.0), (2.0, 2.0), (4.0, 4.0)];
+
+        assert!(back_facing(FrontFace::Clockwise, line));
+        assert!(back_facing(FrontFace::CounterClockwise, line));
+    }
+
+    #[test]
+    fn cull_modes_drop_the_expected_side() {
+        assert!(CullMode::Back.culls(true));
+        assert!(!CullMode::Back.culls(false));
+        assert!(!CullMode::Front.culls(true));
+        assert!(CullMode::Front.culls(false));
+        assert!(!CullMode::None.culls(true));
+        assert!(!CullMode::None.culls(false));
+
+        assert_eq!(CullMode::Back.next().next().next(), CullMode::Back);
+    }
+
+    #[test]
+    fn stencil_ops_clamp_or_wrap_at_the_ends() {
+        let cases = [
+            (StencilOp::Keep, [0, 7, 255]),
+            (StencilOp::Zero, [0, 0, 0]),
+            (StencilOp::Replace, [9, 9, 9]),
+            (StencilOp::IncrementClamp, [1, 8, 255]),
+            (StencilOp::DecrementClamp, [0, 6, 254]),
+            (StencilOp::Invert, [255, 248, 0]),
+            (StencilOp::IncrementWrap, [1, 8, 0]),
+            (StencilOp::DecrementWrap, [255, 6, 254]),
+        ];
+
+        for (op, expected) in cases {
+            assert_eq!([0, 7, 255].map(|s| op.apply(s, 9)), expected, "{op:?}");
+        }
+    }
+
+    #[test]
+    fn stencil_update_keeps_bits_outside_the_write_mask() {
+        let state = StencilState {
+            write_mask: 0x0F,
+            reference: 0xAB,
+            ..StencilState::default()
+        };
+
+        assert_eq!(state.update(0xF0, StencilOp::Replace), 0xFB);
+        assert_eq!(state.update(0xFF, StencilOp::IncrementWrap), 0xF0);
+    }
+
+    #[test]
+    fn stencil_test_compares_through_the_read_mask() {
+        let state = StencilState {
+            enabled: true,
+            compare: CompareFunc::Equal,
+            reference: 0x35,
+            read_mask: 0x0F,
+            ..StencilState::default()
+        };
+
+        assert!(state.test(0x05));
+        assert!(state.test(0xF5));
+        assert!(!state.test(0x34));
+    }
+}
@@ -0,0 +1,98 @@
+use std::{ops::AddAssign, time::Duration};
+
+/// Work counters and stage timings accumulated by
+/// [`crate::renderer::Renderer`] since the last
+/// [`crate::renderer::Renderer::begin_frame`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    /// Mesh triangles handed to a draw call. Each is then either culled,
+    /// rasterized, or entirely outside the near and far planes, so
+    /// `triangles_culled + triangles_rasterized` is at most this.
+    pub triangles_submitted: u64,
+    /// Triangles dropped by face culling.
+    pub triangles_culled: u64,
+    /// Triangles trimmed by the near or far plane with part of them left
+    /// to draw. Those entirely outside are not counted.
+    pub triangles_clipped: u64,
+    /// Triangles handed to the rasterizer, counted once even when clipping
+    /// splits them into several pieces.
+    pub triangles_rasterized: u64,
+    /// Covered pixels that had a colour computed. Multisampling still
+    /// shades once per pixel.
+    pub pixels_shaded: u64,
+    /// Fragments that reached the stencil and depth tests, including those
+    /// the stencil test rejects. While multisampling, every covered sample
+    /// counts as a fragment, so this can be up to the sample count times
+    /// `pixels_shaded`.
+    pub fragments_tested: u64,
+    /// Fragments that passed both the stencil and the depth test, counted
+    /// per sample like `fragments_tested`.
+    pub fragments_passed_depth: u64,
+    /// Fragments whose colour was stored: in the 8-bit buffer, the HDR
+    /// buffer or a sample. Depth writes are separate; with
+    /// [`crate::state::DepthState::write`] off, fragments are still
+    /// written here without updating the depth buffer.
+    pub fragments_written: u64,
+    /// Only measured while [`crate::renderer::Renderer::profile_stages`] is
+    /// set, since timing every triangle has a cost of its own.
+    pub timings: StageTimings,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageTimings {
+    /// Transforming mesh vertices into view space.
+    pub vertex: Duration,
+    /// Clipping, projection and culling per triangle.
+    pub setup: Duration,
+    /// Scan conversion, shading and depth testing.
+    pub raster: Duration,
+    /// Handing the finished frame to the display, recorded by the frontend.
+    pub present: Duration,
+}
+
+impl AddAssign for RenderStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.triangles_submitted += rhs.triangles_submitted;
+        self.triangles_culled += rhs.triangles_culled;
+        self.triangles_clipped += rhs.triangles_clipped;
+        self.triangles_rasterized += rhs.triangles_rasterized;
+        self.pixels_shaded += rhs.pixels_shaded;
+        self.fragments_tested += rhs.fragments_tested;
+        self.fragments_passed_depth += rhs.fragments_passed_depth;
+        self.fragments_written += rhs.fragments_written;
+        self.timings += rhs.timings;
+    }
+}
+
+impl AddAssign for StageTimings {
+    fn add_assign(&mut self, rhs: Self) {
+        self.vertex += rhs.vertex;
+        self.setup += rhs.setup;
+        self.raster += rhs.raster;
+        self.present += rhs.present;
+    }
+}
+
+impl RenderStats {
+    /// A one-line summary, e.g. for a window title.
+    pub fn summary(&self) -> String {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let t = &self.timings;
+
+        format!(
+            "tris {} sub / {} cull / {} clip / {} drawn | frags {} tested / {} depth / {} written | \
+             vtx {:.2} setup {:.2} raster {:.2} present {:.2} ms",
+            self.triangles_submitted,
+            self.triangles_culled,
+            self.triangles_clipped,
+            self.triangles_rasterized,
+            self.fragments_tested,
+            self.fragments_passed_depth,
+            self.fragments_written,
+            ms(t.vertex),
+            ms(t.setup),
+            ms(t.raster),
+            ms(t.present),
+        )
+    }
+}
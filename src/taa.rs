@@ -0,0 +1,354 @@
+use crate::{
+    camera::Camera,
+    primitives::{colour::Colour, matrix::Mat4, vertex::Vertex},
+    renderer::Renderer,
+};
+
+/// Jitter positions cycled through before repeating.
+const JITTER_PHASES: usize = 16;
+
+/// Temporal anti-aliasing: the camera is shifted by a different sub-pixel
+/// offset every frame, and each frame is blended into a history of earlier
+/// ones, so edges converge to their coverage over several frames.
+///
+/// Per frame, call [`Taa::jitter`] on the camera before drawing and
+/// [`Taa::resolve`] on the finished colour buffer.
+#[derive(Debug, Clone)]
+pub struct Taa {
+    /// Weight of the new frame against the history, from 0 to 1.
+    pub blend: f32,
+    /// Linear-light colour of the previous output.
+    history: Vec<[f32; 4]>,
+    /// The camera the history was drawn with, without its jitter, so the
+    /// history is treated as centred on the pixel grid.
+    previous: Option<Camera>,
+    frame: usize,
+}
+
+impl Default for Taa {
+    fn default() -> Self {
+        Self {
+            blend: 0.1,
+            history: Vec::new(),
+            previous: None,
+            frame: 0,
+        }
+    }
+}
+
+impl Taa {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets the history, e.g. after a cut.
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.previous = None;
+    }
+
+    /// Sets `camera.jitter` to this frame's offset from a Halton (2, 3)
+    /// sequence, within half a pixel of the centre.
+    pub fn jitter(&mut self, camera: &mut Camera) {
+        let i = self.frame % JITTER_PHASES + 1;
+        camera.jitter = (halton(i, 2) - 0.5, halton(i, 3) - 0.5);
+        self.frame += 1;
+    }
+
+    /// Blends the renderer's colour buffer with the history and writes the
+    /// result back. Each pixel finds its history by reprojecting its depth
+    /// from `camera`, the one the frame was drawn with, into the previous
+    /// frame's camera. History colours outside the range of the pixel's
+    /// 3x3 neighbourhood are clamped into it, which stops ghosting where
+    /// things moved or appeared.
+    pub fn resolve(&mut self, renderer: &mut Renderer, camera: &Camera) {
+        let (w, h) = (renderer.width, renderer.height);
+        let current: Vec<[f32; 4]> = renderer
+            .buffer
+            .chunks_exact(4)
+            .map(|px| Colour::new(px[0], px[1], px[2], px[3]).to_linear())
+            .collect();
+
+        let unjittered = Camera {
+            jitter: (0.0, 0.0),
+            ..camera.clone()
+        };
+
+        let Some(previous) = self.previous.replace(unjittered) else {
+            self.history = current;
+            return;
+        };
+        if self.history.len() != current.len() {
+            self.history = current;
+            return;
+        }
+
+        let Some(reprojection) = Reprojection::new(camera, &previous) else {
+            self.history = current;
+            return;
+        };
+
+        let mode = renderer.depth_state.mode;
+        let near = renderer.depth_near();
+        let mut output = vec![[0.0; 4]; current.len()];
+
+        for y in 0..h {
+            for x in 0..w {
+                let i = y * w + x;
+
+                // Empty pixels are treated as far away, so turning the
+                // camera still moves the background.
+                let depth = mode.decode(renderer.depth[i], near).min(camera.far);
+                let history = reprojection
+                    .project(x as f64, y as f64, depth)
+                    .and_then(|(px, py)| sample(&self.history, w, h, px, py));
+
+                output[i] = match history {
+                    Some(history) => {
+                        let (lowest, highest) = neighbourhood_bounds(&current, w, h, x, y);
+                        std::array::from_fn(|c| {
+                            let clamped = history[c].clamp(lowest[c], highest[c]);
+                            clamped + (current[i][c] - clamped) * self.blend
+                        })
+                    }
+                    None => current[i],
+                };
+            }
+        }
+
+        for (out, px) in renderer.buffer.chunks_exact_mut(4).zip(&output) {
+            let colour = Colour::from_linear(*px);
+            out.copy_from_slice(&[colour.red, colour.green, colour.blue, colour.alpha]);
+        }
+
+        self.history = output;
+    }
+}
+
+/// Maps a pixel of the frame being resolved to where the same surface was
+/// on screen in the previous frame.
+struct Reprojection {
+    /// The camera the frame was drawn with, jitter included.
+    current: Camera,
+    projection: Mat4,
+    /// Current view space to the previous frame's view space.
+    to_previous: Mat4,
+    /// The previous frame's camera, without jitter.
+    previous: Camera,
+    previous_projection: Mat4,
+}
+
+impl Reprojection {
+    fn new(current: &Camera, previous: &Camera) -> Option<Self> {
+        let to_world = current.view_matrix().inverse()?;
+
+        Some(Self {
+            current: current.clone(),
+            projection: current.projection_matrix(),
+            to_previous: previous.view_matrix() * to_world,
+            previous: previous.clone(),
+            previous_projection: previous.projection_matrix(),
+        })
+    }
+
+    /// The previous screen position of the surface at pixel `x`, `y` and
+    /// view depth `depth`, or None if it was behind the previous camera.
+    fn project(&self, x: f64, y: f64, depth: f64) -> Option<(f64, f64)> {
+        let view = self.current.screen_to_view(&self.projection, x, y, depth);
+        let past = self.to_previous.transform_point(view);
+        if past.z <= 0.0 {
+            return None;
+        }
+
+        let vertex = Vertex::new(past.x, past.y, past.z, 0.0, 0.0);
+        let p = self
+            .previous
+            .view_to_screen(&self.previous_projection, &vertex);
+        Some((p.x, p.y))
+    }
+}
+
+/// Element `index` of the van der Corput sequence in `base`, in 0-1.
+fn halton(mut index: usize, base: usize) -> f64 {
+    let mut result = 0.0;
+    let mut fraction = 1.0;
+
+    while index > 0 {
+        fraction /= base as f64;
+        result += fraction * (index % base) as f64;
+        index /= base;
+    }
+
+    result
+}
+
+/// Bilinear sample of `image` at a screen position, or None off screen.
+fn sample(image: &[[f32; 4]], w: usize, h: usize, x: f64, y: f64) -> Option<[f32; 4]> {
+    if x < 0.0 || y < 0.0 || x > (w - 1) as f64 || y > (h - 1) as f64 {
+        return None;
+    }
+
+    let (x0, y0) = (x as usize, y as usize);
+    let x1 = (x0 + 1).min(w - 1);
+    let y1 = (y0 + 1).min(h - 1);
+    let (tx, ty) = ((x - x0 as f64) as f32, (y - y0 as f64) as f32);
+
+    let (a, b) = (image[y0 * w + x0], image[y0 * w + x1]);
+    let (c, d) = (image[y1 * w + x0], image[y1 * w + x1]);
+
+    Some(std::array::from_fn(|i| {
+        let top = a[i] + (b[i] - a[i]) * tx;
+        let bottom = c[i] + (d[i] - c[i]) * tx;
+        top + (bottom - top) * ty
+    }))
+}
+
+/// Per-channel minimum and maximum over the 3x3 block around a pixel.
+fn neighbourhood_bounds(
+    image: &[[f32; 4]],
+    w: usize,
+    h: usize,
+    x: usize,
+    y: usize,
+) -> ([f32; 4], [f32; 4]) {
+    let mut lowest = [f32::INFINITY; 4];
+    let mut highest = [f32::NEG_INFINITY; 4];
+
+    for ny in y.saturating_sub(1)..=(y + 1).min(h - 1) {
+        for nx in x.saturating_sub(1)..=(x + 1).min(w - 1) {
+            let px = image[ny * w + nx];
+            for c in 0..4 {
+                lowest[c] = lowest[c].min(px[c]);
+                highest[c] = highest[c].max(px[c]);
+            }
+        }
+    }
+
+    (lowest, highest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::vector::Vec3;
+
+    fn assert_close(a: (f64, f64), b: (f64, f64)) {
+        assert!(
+            (a.0 - b.0).abs() < 1e-6 && (a.1 - b.1).abs() < 1e-6,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    fn camera() -> Camera {
+        let mut camera = Camera::new(40, 30);
+        camera.position = Vec3::new(1.0, 2.0, -5.0);
+        camera.look_at(Vec3::new(0.0, 0.0, 3.0));
+        camera
+    }
+
+    #[test]
+    fn halton_values() {
+        assert_eq!(halton(0, 2), 0.0);
+        assert_eq!(halton(1, 2), 0.5);
+        assert_eq!(halton(2, 2), 0.25);
+        assert_eq!(halton(3, 2), 0.75);
+        assert_eq!(halton(4, 2), 0.125);
+        assert!((halton(1, 3) - 1.0 / 3.0).abs() < 1e-12);
+        assert!((halton(2, 3) - 2.0 / 3.0).abs() < 1e-12);
+        assert!((halton(3, 3) - 1.0 / 9.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn jitter_stays_within_half_a_pixel() {
+        let mut taa = Taa::new();
+        let mut camera = camera();
+
+        for _ in 0..JITTER_PHASES * 2 {
+            taa.jitter(&mut camera);
+            assert!(camera.jitter.0.abs() < 0.5 && camera.jitter.1.abs() < 0.5);
+        }
+    }
+
+    #[test]
+    fn sample_blends_between_pixels() {
+        let image = [
+            [0.0, 0.0, 0.0, 1.0],
+            [1.0, 0.0, 0.0, 1.0],
+            [0.0, 1.0, 0.0, 1.0],
+            [1.0, 1.0, 1.0, 1.0],
+        ];
+
+        assert_eq!(sample(&image, 2, 2, 0.0, 0.0), Some(image[0]));
+        assert_eq!(sample(&image, 2, 2, 1.0, 1.0), Some(image[3]));
+        assert_eq!(sample(&image, 2, 2, 0.5, 0.0), Some([0.5, 0.0, 0.0, 1.0]));
+        assert_eq!(sample(&image, 2, 2, 0.5, 0.5), Some([0.5, 0.5, 0.25, 1.0]));
+    }
+
+    #[test]
+    fn sample_off_screen_is_none() {
+        let image = [[1.0; 4]; 4];
+
+        assert_eq!(sample(&image, 2, 2, -0.01, 0.5), None);
+        assert_eq!(sample(&image, 2, 2, 0.5, -0.01), None);
+        assert_eq!(sample(&image, 2, 2, 1.01, 0.5), None);
+        assert_eq!(sample(&image, 2, 2, 0.5, 1.01), None);
+    }
+
+    #[test]
+    fn neighbourhood_bounds_cover_the_3x3_block() {
+        let (w, h) = (4, 3);
+        let image: Vec<[f32; 4]> = (0..w * h)
+            .map(|i| [i as f32, -(i as f32), 0.5, 1.0])
+            .collect();
+
+        let (lowest, highest) = neighbourhood_bounds(&image, w, h, 1, 1);
+        assert_eq!(lowest, [0.0, -10.0, 0.5, 1.0]);
+        assert_eq!(highest, [10.0, 0.0, 0.5, 1.0]);
+
+        // Corners only see the pixels that exist.
+        let (lowest, highest) = neighbourhood_bounds(&image, w, h, 3, 2);
+        assert_eq!(lowest, [6.0, -11.0, 0.5, 1.0]);
+        assert_eq!(highest, [11.0, -6.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn static_camera_maps_each_pixel_to_itself() {
+        let camera = camera();
+        let reprojection = Reprojection::new(&camera, &camera).unwrap();
+
+        for (x, y, depth) in [(0.0, 0.0, 1.0), (20.0, 15.0, 8.0), (39.0, 7.0, 400.0)] {
+            assert_close(reprojection.project(x, y, depth).unwrap(), (x, y));
+        }
+    }
+
+    #[test]
+    fn static_camera_removes_this_frames_jitter() {
+        // The history is drawn without jitter, so a jittered pixel lands
+        // where its surface sits on the unjittered grid.
+        let previous = camera();
+        let mut current = previous.clone();
+        current.jitter = (0.25, -0.375);
+        let reprojection = Reprojection::new(&current, &previous).unwrap();
+
+        for (x, y, depth) in [(0.0, 0.0, 1.0), (20.0, 15.0, 8.0), (39.0, 7.0, 400.0)] {
+            assert_close(
+                reprojection.project(x, y, depth).unwrap(),
+                (x - 0.25, y + 0.375),
+            );
+        }
+    }
+
+    #[test]
+    fn moving_the_camera_moves_the_history() {
+        let previous = Camera::new(40, 30);
+        let mut current = previous.clone();
+        current.position.x += 1.0;
+        let reprojection = Reprojection::new(&current, &previous).unwrap();
+
+        // A surface straight ahead was further right before the camera
+        // stepped right.
+        let (x, y) = reprojection.project(20.0, 15.0, 10.0).unwrap();
+        assert!(x > 20.0, "{x}");
+        assert!((y - 15.0).abs() < 1e-6, "{y}");
+    }
+}
@@ -0,0 +1,239 @@
+use std::mem::swap;
+
+use crate::{
+    msaa::{Msaa, SampleBuffers},
+    primitives::{
+        colour::Colour,
+        texture::{ColourSpace, Filter, Texture},
+    },
+    renderer::Renderer,
+    state::DepthMode,
+};
+
+/// Colour, depth and stencil buffers that can be drawn into instead of the
+/// renderer's own, e.g. to render a mirror or a screen inside the scene.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderTarget {
+    pub width: usize,
+    pub height: usize,
+    /// RGBA, four bytes per pixel, like [`Renderer::buffer`].
+    pub buffer: Vec<u8>,
+    /// Linear-light colour, as in [`Renderer::hdr`].
+    pub hdr: Option<Vec<[f32; 4]>>,
+    /// Multisample buffers, as in [`Renderer::samples`].
+    pub samples: Option<SampleBuffers>,
+    pub depth: Vec<f64>,
+    /// How `depth` and the sample depths are encoded.
+    pub depth_mode: DepthMode,
+    pub stencil: Vec<u8>,
+}
+
+impl RenderTarget {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            buffer: vec![0; width * height * 4],
+            hdr: None,
+            samples: None,
+            depth: vec![DepthMode::default().clear_value(); width * height],
+            depth_mode: DepthMode::default(),
+            stencil: vec![0; width * height],
+        }
+    }
+
+    /// Changes the size, discarding the contents. HDR and multisample
+    /// buffers and the depth encoding are kept.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let hdr = self.hdr.is_some();
+        let msaa = self.samples.as_ref().map(|s| s.msaa);
+        let mode = self.depth_mode;
+        *self = Self::new(width, height);
+
+        self.depth_mode = mode;
+        self.depth.fill(mode.clear_value());
+        if hdr {
+            self.hdr = Some(vec![[0.0, 0.0, 0.0, 1.0]; width * height]);
+        }
+        if let Some(msaa) = msaa {
+            self.samples = Some(SampleBuffers::new(msaa, width * height, mode.clear_value()));
+        }
+    }
+
+    /// Gives the target the depth encoding, HDR buffer and multisample
+    /// buffers that `renderer` draws with. Buffers that have to be created
+    /// or re-encoded start out cleared; the rest keep their contents.
+    pub fn match_format(&mut self, renderer: &Renderer) {
+        let pixels = self.width * self.height;
+        let mode = renderer.depth_state.mode;
+
+        if self.depth_mode != mode {
+            self.depth_mode = mode;
+            self.depth.fill(mode.clear_value());
+            if let Some(samples) = &mut self.samples {
+                samples.depth.fill(mode.clear_value());
+            }
+        }
+
+        if renderer.hdr.is_some() != self.hdr.is_some() {
+            self.hdr = renderer
+                .hdr
+                .as_ref()
+                .map(|_| vec![[0.0, 0.0, 0.0, 1.0]; pixels]);
+        }
+
+        let msaa = renderer.msaa();
+        if self.samples.as_ref().map_or(Msaa::Off, |s| s.msaa) != msaa {
+            self.samples =
+                (msaa != Msaa::Off).then(|| SampleBuffers::new(msaa, pixels, mode.clear_value()));
+        }
+    }
+
+    /// Copies the colour buffer into a texture that materials can sample.
+    /// Row 0, the top of the image, is `v = 0`.
+    pub fn to_texture(&self) -> Texture {
+        Texture {
+            width: self.width,
+            height: self.height,
+            data: self
+                .buffer
+                .chunks_exact(4)
+                .map(|px| Colour::new(px[0], px[1], px[2], px[3]))
+                .collect(),
+            path: None,
+            colour_space: ColourSpace::Srgb,
+            filter: Filter::default(),
+        }
+    }
+}
+
+impl Renderer {
+    /// Exchanges the renderer's buffers and size with `target`'s, so that
+    /// drawing goes into the target. Calling it again with the same target
+    /// swaps them back. [`Renderer::render_to`] does both.
+    ///
+    /// The exchange is raw: the renderer takes on whatever HDR and
+    /// multisample buffers the target has, or lacks, and its `depth_state`
+    /// is left alone. Use [`RenderTarget::match_format`] first to keep them
+    /// consistent.
+    pub fn swap_target(&mut self, target: &mut RenderTarget) {
+        swap(&mut self.width, &mut target.width);
+        swap(&mut self.height, &mut target.height);
+        swap(&mut self.buffer, &mut target.buffer);
+        swap(&mut self.hdr, &mut target.hdr);
+        swap(&mut self.samples, &mut target.samples);
+        swap(&mut self.depth, &mut target.depth);
+        swap(&mut self.stencil, &mut target.stencil);
+    }
+
+    /// Runs `draw` with `target` bound in place of the renderer's own
+    /// buffers. Cameras used inside should match the target's size.
+    ///
+    /// The target is first given the renderer's depth encoding, HDR and
+    /// multisampling with [`RenderTarget::match_format`], so drawing into
+    /// it looks the same as drawing to the screen. With HDR or MSAA on,
+    /// call [`Renderer::resolve`] inside `draw` to fill `target.buffer`.
+    pub fn render_to<R>(
+        &mut self,
+        target: &mut RenderTarget,
+        draw: impl FnOnce(&mut Renderer) -> R,
+    ) -> R {
+        target.match_format(self);
+        self.swap_target(target);
+        let result = draw(self);
+        self.swap_target(target);
+        result
+    }
+
+    /// Copies the current colour, depth and stencil buffers into a new
+    /// target.
+    pub fn to_target(&self) -> RenderTarget {
+        RenderTarget {
+            width: self.width,
+            height: self.height,
+            buffer: self.buffer.clone(),
+            hdr: self.hdr.clone(),
+            samples: self.samples.clone(),
+            depth: self.depth.clone(),
+            depth_mode: self.depth_state.mode,
+            stencil: self.stencil.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::DepthState;
+
+    const RED: Colour = Colour::new(255, 0, 0, 255);
+
+    fn reversed_renderer() -> Renderer {
+        let mut renderer = Renderer::new(8, 8);
+        renderer.depth_state = DepthState::with_mode(DepthMode::ReversedZ);
+        renderer.clear(Colour::new(0, 0, 0, 255));
+        renderer
+    }
+
+    #[test]
+    fn new_target_is_cleared_for_the_renderers_depth_mode() {
+        let mut renderer = reversed_renderer();
+        let mut target = RenderTarget::new(4, 4);
+
+        renderer.render_to(&mut target, |r| r.put_pixel_depth(1, 1, 5.0, RED));
+
+        assert_eq!(target.depth_mode, DepthMode::ReversedZ);
+        assert_eq!(&target.buffer[(4 + 1) * 4..(4 + 2) * 4], &[255, 0, 0, 255]);
+        assert!(
+            target
+                .depth
+                .iter()
+                .enumerate()
+                .all(|(i, &d)| (i == 5) == (d > 0.0)),
+            "only the drawn pixel should leave the reversed clear value"
+        );
+    }
+
+    #[test]
+    fn render_to_draws_with_the_renderers_hdr_and_msaa() {
+        let mut renderer = reversed_renderer();
+        renderer.set_hdr(true);
+        renderer.set_msaa(Msaa::X4);
+        let mut target = RenderTarget::new(4, 4);
+
+        renderer.render_to(&mut target, |r| {
+            assert_eq!((r.width, r.height), (4, 4));
+            assert_eq!(r.hdr.as_ref().map(Vec::len), Some(16));
+            assert_eq!(r.msaa(), Msaa::X4);
+            assert!(r.samples.as_ref().unwrap().depth.iter().all(|&d| d == 0.0));
+        });
+
+        // The renderer gets its own buffers back.
+        assert_eq!((renderer.width, renderer.height), (8, 8));
+        assert_eq!(renderer.hdr.as_ref().map(Vec::len), Some(64));
+        assert_eq!(renderer.samples.as_ref().unwrap().colour.len(), 64 * 4);
+
+        // Turning them off in the renderer drops them from the target too.
+        renderer.set_hdr(false);
+        renderer.set_msaa(Msaa::Off);
+        renderer.render_to(&mut target, |r| {
+            assert!(r.hdr.is_none());
+            assert_eq!(r.msaa(), Msaa::Off);
+        });
+    }
+
+    #[test]
+    fn resize_keeps_the_format() {
+        let mut renderer = reversed_renderer();
+        renderer.set_msaa(Msaa::X2);
+        let mut target = renderer.to_target();
+
+        target.resize(3, 2);
+
+        assert_eq!(target.depth_mode, DepthMode::ReversedZ);
+        assert!(target.depth.iter().all(|&d| d == 0.0));
+        let samples = target.samples.as_ref().unwrap();
+        assert_eq!((samples.msaa, samples.depth.len()), (Msaa::X2, 12));
+        assert!(samples.depth.iter().all(|&d| d == 0.0));
+    }
+}
@@ -0,0 +1,95 @@
+use crate::primitives::{matrix::Mat4, quaternion::Quat, vector::Vec3};
+
+/// Places an object relative to its parent: scale, then rotation (about `pivot` if set,
+/// otherwise the mesh origin), then translation.
+///
+/// `pivot` is a point in the mesh's own, already scaled space, so it moves
+/// with `translation`. Unlike the old world-space pivot, rotating never
+/// orbits the mesh around a fixed point in the scene.
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+    pub pivot: Option<Vec3>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: Vec3::default(),
+            rotation: Quat::IDENTITY,
+            scale: Vec3::ONE,
+            pivot: None,
+        }
+    }
+}
+
+impl Transform {
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self {
+            translation,
+            ..Self::default()
+        }
+    }
+
+    /// Applies an extra rotation on top of the current one, about world axes.
+    pub fn rotate(&mut self, rotation: Quat) {
+        self.rotation = (rotation * self.rotation).normalize();
+    }
+
+    pub fn model_matrix(&self) -> Mat4 {
+        let rotate = match self.pivot {
+            Some(p) => {
+                Mat4::translation(p) * Mat4::from_quat(self.rotation) * Mat4::translation(-p)
+            }
+            None => Mat4::from_quat(self.rotation),
+        };
+
+        Mat4::translation(self.translation) * rotate * Mat4::scaling(self.scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-9, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn scales_then_rotates_then_translates() {
+        let transform = Transform {
+            translation: Vec3::new(10.0, 0.0, 0.0),
+            rotation: Quat::from_axis_angle(Vec3::Z, FRAC_PI_2),
+            scale: Vec3::new(2.0, 2.0, 2.0),
+            pivot: None,
+        };
+
+        let p = transform
+            .model_matrix()
+            .transform_point(Vec3::new(1.0, 0.0, 0.0));
+        assert_close(p, Vec3::new(10.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn pivot_is_in_local_space_and_stays_fixed() {
+        let pivot = Vec3::new(1.0, 1.0, 0.0);
+        let transform = Transform {
+            translation: Vec3::new(0.0, 0.0, 5.0),
+            rotation: Quat::from_axis_angle(Vec3::Z, FRAC_PI_2),
+            pivot: Some(pivot),
+            ..Transform::default()
+        };
+        let m = transform.model_matrix();
+
+        assert_close(m.transform_point(pivot), Vec3::new(1.0, 1.0, 5.0));
+        assert_close(
+            m.transform_point(Vec3::new(2.0, 1.0, 0.0)),
+            Vec3::new(1.0, 2.0, 5.0),
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use std::{
+    fs::metadata,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Detects changes to a set of files by polling their modification times.
+#[derive(Debug, Default)]
+pub struct FileWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl FileWatcher {
+    pub fn new(paths: &[PathBuf]) -> Self {
+        let mut watcher = Self::default();
+        watcher.watch(paths);
+        watcher
+    }
+
+    /// Replaces the watched set, recording the current modification times.
+    pub fn watch(&mut self, paths: &[PathBuf]) {
+        self.files = paths
+            .iter()
+            .map(|path| (path.clone(), modified(path)))
+            .collect();
+    }
+
+    /// Returns true if any watched file was modified, created or removed
+    /// since the last poll.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+
+        for (path, stamp) in self.files.iter_mut() {
+            let current = modified(path);
+            if current != *stamp {
+                *stamp = current;
+                changed = true;
+            }
+        }
+
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{self, File},
+        time::Duration,
+    };
+
+    use super::*;
+
+    fn touch(path: &Path, seconds: u64) {
+        File::create(path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+            .unwrap();
+    }
+
+    #[test]
+    fn poll_reports_each_change_once() {
+        let dir =
+            std::env::temp_dir().join(format!("software_renderer_watcher_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (existing, missing) = (dir.join("a.obj"), dir.join("b.mtl"));
+        touch(&existing, 1);
+
+        let mut watcher = FileWatcher::new(&[existing.clone(), missing.clone()]);
+        assert!(!watcher.poll());
+
+        touch(&existing, 2);
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+
+        touch(&missing, 1);
+        assert!(watcher.poll());
+
+        fs::remove_file(&existing).unwrap();
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+
+        // Watching a new set forgets the old files.
+        watcher.watch(std::slice::from_ref(&missing));
+        touch(&existing, 3);
+        assert!(!watcher.poll());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
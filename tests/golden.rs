@@ -0,0 +1,313 @@
+//! Renders every model in `assets/objects` from fixed cameras, and one
+//! textured model with each rendering option, and compares the result with
+//! the reference PNGs in `tests/golden`.
+//!
+//! Run with `UPDATE_GOLDEN=1` to rewrite the references after an intended
+//! change to the output. On failure the actual image and a diff image are
+//! written under the cargo target directory.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use image::{Rgba, RgbaImage};
+use software_renderer::{
+    camera::Camera,
+    hdr::ToneMapper,
+    headless::{HeadlessOptions, render, render_rgba},
+    loader::load_wavefront,
+    msaa::Msaa,
+    post::AntiAliasing,
+    primitives::{colour::Colour, quaternion::Quat, texture::Filter, vector::Vec3},
+};
+
+const SIZE: usize = 128;
+
+/// Largest per-channel difference a pixel may have before it counts as
+/// changed.
+const TOLERANCE: u8 = 2;
+
+/// Models that cannot be loaded from this repository, with the reason.
+const SKIPPED: &[(&str, &str)] = &[(
+    "skull.obj",
+    "skull.mtl points at an absolute texture path that is not checked in",
+)];
+
+/// The model sits this many bounding radii in front of the origin.
+const DISTANCE: f64 = 2.2;
+
+struct View {
+    name: &'static str,
+    /// Model rotation in degrees.
+    model_rotation: (f64, f64, f64),
+    /// Camera position as a fraction of the model's distance. The camera
+    /// always aims at the model.
+    camera_offset: Vec3,
+}
+
+const VIEWS: &[View] = &[
+    View {
+        name: "front",
+        model_rotation: (0.0, 0.0, 0.0),
+        camera_offset: Vec3::new(0.0, 0.0, 0.0),
+    },
+    View {
+        name: "angled",
+        model_rotation: (20.0, 35.0, 0.0),
+        camera_offset: Vec3::new(0.5, 0.6, 0.0),
+    },
+];
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn failure_dir() -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden")
+}
+
+fn aimed_rotation(position: Vec3, target: Vec3) -> (f32, f32, f32) {
+    let mut camera = Camera::new(SIZE, SIZE);
+    camera.position = position;
+    camera.look_at(target);
+    camera.rotation
+}
+
+fn view_options(model: &Path, view: &View) -> HeadlessOptions {
+    let mesh = load_wavefront(model).unwrap();
+    let target = Vec3::new(0.0, 0.0, mesh.bounding_radius() * DISTANCE);
+    let camera_position = view.camera_offset * target.z;
+    let (x, y, z) = view.model_rotation;
+
+    HeadlessOptions {
+        model: model.to_path_buf(),
+        width: SIZE,
+        height: SIZE,
+        model_position: target,
+        model_rotation: Quat::from_euler(x.to_radians(), y.to_radians(), z.to_radians()),
+        camera_position,
+        camera_rotation: aimed_rotation(camera_position, target),
+        background: Colour::new(20, 20, 40, 255),
+        ..HeadlessOptions::default()
+    }
+}
+
+fn render_image(options: &HeadlessOptions) -> RgbaImage {
+    let rgba = render_rgba(options)
+        .unwrap_or_else(|e| panic!("failed to render {}: {e}", options.model.display()));
+
+    RgbaImage::from_raw(SIZE as u32, SIZE as u32, rgba).unwrap()
+}
+
+fn render_view(model: &Path, view: &View) -> RgbaImage {
+    render_image(&view_options(model, view))
+}
+
+fn model_path(model: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("assets/objects")
+        .join(model)
+}
+
+/// Returns the number of pixels outside tolerance and an image marking them
+/// in red over a dimmed copy of the reference.
+fn compare(expected: &RgbaImage, actual: &RgbaImage) -> (usize, RgbaImage) {
+    let mut diff = RgbaImage::new(expected.width(), expected.height());
+    let mut failures = 0;
+
+    for (x, y, e) in expected.enumerate_pixels() {
+        let a = actual.get_pixel(x, y);
+        let delta = (0..4).map(|i| e[i].abs_diff(a[i])).max().unwrap();
+
+        let marked = if delta > TOLERANCE {
+            failures += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let grey = ((e[0] as u32 + e[1] as u32 + e[2] as u32) / 12) as u8;
+            Rgba([grey, grey, grey, 255])
+        };
+
+        diff.put_pixel(x, y, marked);
+    }
+
+    (failures, diff)
+}
+
+/// Compares `actual` with the reference for `case`, or replaces the
+/// reference with it under `UPDATE_GOLDEN`. Returns a description of any
+/// mismatch.
+fn check_case(case: &str, actual: &RgbaImage) -> Option<String> {
+    let reference = golden_dir().join(format!("{case}.png"));
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        actual.save(&reference).unwrap();
+        return None;
+    }
+
+    let expected = match image::open(&reference) {
+        Ok(image) => image.to_rgba8(),
+        Err(e) => {
+            return Some(format!(
+                "{case}: cannot read {} ({e}); run with UPDATE_GOLDEN=1",
+                reference.display()
+            ));
+        }
+    };
+
+    if expected.dimensions() != actual.dimensions() {
+        return Some(format!(
+            "{case}: reference is {:?} but the render is {:?}",
+            expected.dimensions(),
+            actual.dimensions()
+        ));
+    }
+
+    let (failures, diff) = compare(&expected, actual);
+    if failures == 0 {
+        return None;
+    }
+
+    let out = failure_dir();
+    fs::create_dir_all(&out).unwrap();
+    actual.save(out.join(format!("{case}.actual.png"))).unwrap();
+    diff.save(out.join(format!("{case}.diff.png"))).unwrap();
+
+    Some(format!(
+        "{case}: {failures} pixels differ by more than {TOLERANCE}; see {}",
+        out.display()
+    ))
+}
+
+fn check(model: &str) {
+    let path = model_path(model);
+    let stem = model.trim_end_matches(".obj");
+
+    let errors: Vec<String> = VIEWS
+        .iter()
+        .filter_map(|view| check_case(&format!("{stem}_{}", view.name), &render_view(&path, view)))
+        .collect();
+
+    assert!(errors.is_empty(), "{}", errors.join("\n"));
+}
+
+/// Model and view the option tests render, chosen so textures, edges and
+/// highlights are all on screen.
+const OPTION_MODEL: &str = "textured_cube.obj";
+const OPTION_VIEW: usize = 1;
+
+/// Renders [`OPTION_MODEL`] with `configure` applied to the options and
+/// compares it with the `<model>_<view>_<name>` reference.
+fn check_option(name: &str, configure: impl FnOnce(&mut HeadlessOptions)) {
+    let view = &VIEWS[OPTION_VIEW];
+    let mut options = view_options(&model_path(OPTION_MODEL), view);
+    configure(&mut options);
+
+    let stem = OPTION_MODEL.trim_end_matches(".obj");
+    let case = format!("{stem}_{}_{name}", view.name);
+    if let Some(error) = check_case(&case, &render_image(&options)) {
+        panic!("{error}");
+    }
+}
+
+macro_rules! golden_tests {
+    ($($name:ident => $model:literal,)+) => {
+        const COVERED: &[&str] = &[$($model),+];
+
+        $(
+            #[test]
+            fn $name() {
+                check($model);
+            }
+        )+
+    };
+}
+
+golden_tests! {
+    cube => "cube.obj",
+    material_cube => "material_cube.obj",
+    sphere => "sphere.obj",
+    susan => "susan.obj",
+    textured_cube => "textured_cube.obj",
+    top => "top.obj",
+    torus => "torus.obj",
+}
+
+macro_rules! option_tests {
+    ($($name:ident => |$options:ident| $configure:expr,)+) => {
+        $(
+            #[test]
+            fn $name() {
+                check_option(stringify!($name), |$options| $configure);
+            }
+        )+
+    };
+}
+
+option_tests! {
+    msaa_4 => |options| options.msaa = Msaa::X4,
+    hdr_aces => |options| options.hdr = Some(ToneMapper::Aces),
+    aa_fxaa => |options| options.anti_aliasing = AntiAliasing::Fxaa,
+    aa_taa => |options| options.anti_aliasing = AntiAliasing::Taa,
+    scale_half => |options| options.scale = 0.5,
+    filter_bilinear => |options| options.filter = Filter::Bilinear,
+}
+
+#[test]
+fn every_model_is_covered() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/objects");
+
+    for entry in fs::read_dir(dir).unwrap() {
+        let name = entry.unwrap().file_name().to_string_lossy().into_owned();
+        if !name.ends_with(".obj") {
+            continue;
+        }
+
+        assert!(
+            COVERED.contains(&name.as_str()) || SKIPPED.iter().any(|(s, _)| *s == name),
+            "{name} has no golden test; add it to golden_tests! or SKIPPED"
+        );
+    }
+}
+
+#[test]
+fn skipped_models_still_fail_to_load() {
+    for (model, reason) in SKIPPED {
+        assert!(
+            load_wavefront(&model_path(model)).is_err(),
+            "{model} now loads ({reason} no longer applies); give it a golden test"
+        );
+    }
+}
+
+#[test]
+fn camera_roll_turns_the_image() {
+    let model = model_path("susan.obj");
+    let level = render_view(&model, &VIEWS[0]);
+
+    let mesh = load_wavefront(&model).unwrap();
+    let options = HeadlessOptions {
+        model: model.clone(),
+        width: SIZE,
+        height: SIZE,
+        model_position: Vec3::new(0.0, 0.0, mesh.bounding_radius() * DISTANCE),
+        camera_rotation: (0.0, 0.0, std::f32::consts::FRAC_PI_2),
+        background: Colour::new(20, 20, 40, 255),
+        ..HeadlessOptions::default()
+    };
+    let renderer = render(&options).unwrap();
+    let rolled = RgbaImage::from_raw(SIZE as u32, SIZE as u32, renderer.buffer).unwrap();
+
+    // A quarter turn of roll is a quarter turn of the image, up to
+    // rasterization differences along the edges.
+    let (level_failures, _) = compare(&level, &rolled);
+    let (turned_failures, _) = compare(&image::imageops::rotate270(&level), &rolled);
+    assert!(
+        level_failures > SIZE * SIZE / 20,
+        "roll left the image unchanged"
+    );
+    assert!(
+        turned_failures < SIZE * SIZE / 100,
+        "{turned_failures} pixels differ from the turned image"
+    );
+}